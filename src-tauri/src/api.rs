@@ -0,0 +1,457 @@
+//! Opt-in HTTP API on localhost, so editors and scripts can use the models
+//! already downloaded by the app:
+//!
+//! - `POST /v1/audio/transcriptions` and `POST /v1/audio/translations`:
+//!   OpenAI-compatible multipart uploads (`file`, `model`, `language`,
+//!   `prompt`, `response_format` = json | text | srt | vtt | verbose_json)
+//! - `GET /v1/models` (also `/models`)
+//! - `GET /history`
+//!
+//! Every request needs `Authorization: Bearer <api_token>`. Uploads are
+//! streamed to a temp file and go through `transcribe_media`, so they queue
+//! behind any transcription already running.
+
+use crate::commands::audio::transcribe_media;
+use crate::commands::history::get_history;
+use crate::consts::MODELS;
+use crate::session::{load_meta, load_segments};
+use crate::settings::modify_settings;
+use crate::source::MediaSource;
+use crate::state::AppState;
+use crate::transcript::to_vtt;
+use crate::types::{FileTranscribeOptions, MediaSlice, WhisperTask};
+use crate::utils::{get_model_info, get_recordings_dir};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Largest request body accepted (the OpenAI API allows 25 MB; local files are often bigger).
+const MAX_UPLOAD_BYTES: u64 = 200 * 1024 * 1024;
+/// Text fields (model, prompt, ...) are short; this bounds what is held in memory.
+const MAX_FIELD_BYTES: u64 = 64 * 1024;
+/// Requests handled at once; further connections wait for a free worker.
+const WORKERS: usize = 4;
+
+static UPLOAD_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+type Reply = Response<Cursor<Vec<u8>>>;
+type HttpError = (u16, String);
+
+/// Stops the running server and starts a new one if the API is enabled.
+/// Called at startup and whenever the API settings change.
+pub fn restart(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if let Some(server) = state.api_server.lock().unwrap().take() {
+        // Each call releases one worker
+        for _ in 0..WORKERS {
+            server.unblock();
+        }
+    }
+
+    let mut settings = state.settings.lock().unwrap().clone();
+    if !settings.api_enabled {
+        return Ok(());
+    }
+
+    if settings.api_token.is_empty() {
+        settings.api_token = generate_token()?;
+        let token = settings.api_token.clone();
+        modify_settings(app, |s| s.api_token = token)?;
+    }
+
+    let address = settings.api_bind_address.clone();
+    let server = Arc::new(bind(&address)?);
+    *state.api_server.lock().unwrap() = Some(server.clone());
+    println!("[Rust] API server listening on http://{}", address);
+
+    for worker in 0..WORKERS {
+        let app = app.clone();
+        let server = server.clone();
+        let token = settings.api_token.clone();
+        let address = address.clone();
+        thread::spawn(move || {
+            // Ends when `restart` unblocks the server
+            for request in server.incoming_requests() {
+                handle(&app, request, &token);
+            }
+            if worker == 0 {
+                println!("[Rust] API server on {} stopped", address);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// The previous server may still be releasing the port right after a restart.
+fn bind(address: &str) -> Result<Server, String> {
+    let mut attempts = 0;
+    loop {
+        match Server::http(address) {
+            Ok(server) => return Ok(server),
+            Err(_) if attempts < 10 => {
+                attempts += 1;
+                thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => return Err(format!("Failed to start API server on {}: {}", address, e)),
+        }
+    }
+}
+
+/// 128 random bits as hex.
+fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| "Failed to generate an API token".to_string())?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn handle(app: &AppHandle, mut request: Request, token: &str) {
+    let response = if !authorized(&request, token) {
+        error(401, "Invalid or missing API token")
+    } else {
+        route(app, &mut request).unwrap_or_else(|(status, message)| error(status, &message))
+    };
+
+    let _ = request.respond(response);
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    let provided = header_value(request, "Authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+
+    // Constant-time comparison
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn route(app: &AppHandle, request: &mut Request) -> Result<Reply, HttpError> {
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+
+    match (request.method(), path.as_str()) {
+        (Method::Get, "/v1/models" | "/models") => Ok(models(app)),
+        (Method::Get, "/history") => {
+            let history = tauri::async_runtime::block_on(get_history(app.clone(), None))
+                .map_err(|e| (500, e))?;
+            Ok(json_response(200, json!(history)))
+        }
+        (Method::Post, "/v1/audio/transcriptions") => {
+            transcribe(app, request, WhisperTask::Transcribe)
+        }
+        (Method::Post, "/v1/audio/translations") => {
+            transcribe(app, request, WhisperTask::Translate)
+        }
+        (method, _) => Err((404, format!("No route for {} {}", method, path))),
+    }
+}
+
+fn models(app: &AppHandle) -> Reply {
+    let data: Vec<_> = MODELS
+        .iter()
+        .map(|(name, _)| {
+            let downloaded = get_model_info(app, name)
+                .map(|(path, _)| path.exists())
+                .unwrap_or(false);
+            json!({ "id": name, "object": "model", "owned_by": "local", "downloaded": downloaded })
+        })
+        .collect();
+
+    json_response(200, json!({ "object": "list", "data": data }))
+}
+
+fn transcribe(
+    app: &AppHandle,
+    request: &mut Request,
+    task: WhisperTask,
+) -> Result<Reply, HttpError> {
+    let content_type = header_value(request, "Content-Type")
+        .unwrap_or("")
+        .to_string();
+    let boundary = content_type
+        .split(';')
+        .find_map(|part| part.trim().strip_prefix("boundary="))
+        .map(|b| b.trim_matches('"').to_string())
+        .ok_or((400, "Expected a multipart/form-data body".to_string()))?;
+
+    let upload = upload_path(app).map_err(|e| (500, e))?;
+    let result = transcribe_upload(app, request, &boundary, &upload, task);
+    let _ = std::fs::remove_file(&upload);
+    result
+}
+
+/// Hidden file in the recordings folder, which the history and storage scans skip.
+fn upload_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let n = UPLOAD_COUNTER.fetch_add(1, Ordering::SeqCst);
+    Ok(get_recordings_dir(app)?.join(format!(".upload-{}-{}.part", std::process::id(), n)))
+}
+
+fn transcribe_upload(
+    app: &AppHandle,
+    request: &mut Request,
+    boundary: &str,
+    upload: &Path,
+    task: WhisperTask,
+) -> Result<Reply, HttpError> {
+    let mut body = request.as_reader().take(MAX_UPLOAD_BYTES);
+    let form = parse_multipart(&mut body, boundary, upload);
+    if body.limit() == 0 {
+        return Err((413, "Upload too large".into()));
+    }
+    let form = form?;
+    if !form.has_file {
+        return Err((400, "Missing `file` field".to_string()));
+    }
+
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    let field = |name: &str| form.fields.get(name).filter(|v| !v.is_empty()).cloned();
+
+    // OpenAI clients send `whisper-1`; anything we don't know uses the app's model
+    let model_type = field("model")
+        .filter(|m| MODELS.iter().any(|(name, _)| *name == m.as_str()))
+        .unwrap_or(settings.model_type);
+    let response_format = field("response_format").unwrap_or_else(|| "json".into());
+    if !["json", "text", "srt", "vtt", "verbose_json"].contains(&response_format.as_str()) {
+        return Err((
+            400,
+            format!("Unsupported response_format: {}", response_format),
+        ));
+    }
+
+    let options = FileTranscribeOptions {
+        model_type,
+        language: field("language").unwrap_or(settings.language),
+        prompt: field("prompt").unwrap_or_default(),
+        task,
+        with_timestamps: response_format == "srt",
+        diarize: false,
+        max_chars: None,
+        slice: MediaSlice::default(),
+    };
+
+    let source = MediaSource::File(upload.to_string_lossy().to_string());
+    let (id, text) = tauri::async_runtime::block_on(transcribe_media(app, source, &options))
+        .map_err(|e| (500, e))?;
+
+    let session_dir = get_recordings_dir(app).map_err(|e| (500, e))?.join(&id);
+    let segments = load_segments(&session_dir).unwrap_or_default();
+    let no_renames = Default::default();
+
+    Ok(match response_format.as_str() {
+        "text" | "srt" => text_response(text, "text/plain; charset=utf-8"),
+        "vtt" => text_response(to_vtt(&segments, &no_renames), "text/vtt; charset=utf-8"),
+        "verbose_json" => {
+            let meta = load_meta(&session_dir);
+            let task_name = match task {
+                WhisperTask::Translate => "translate",
+                _ => "transcribe",
+            };
+            let segments: Vec<_> = segments
+                .iter()
+                .enumerate()
+                .map(|(i, s)| {
+                    json!({
+                        "id": i,
+                        "start": s.start_ms as f64 / 1000.0,
+                        "end": s.end_ms as f64 / 1000.0,
+                        "text": s.text,
+                    })
+                })
+                .collect();
+            json_response(
+                200,
+                json!({
+                    "task": task_name,
+                    "language": meta.language,
+                    "duration": segments.last().map(|s| s["end"].clone()),
+                    "text": text,
+                    "segments": segments,
+                    "id": id,
+                }),
+            )
+        }
+        _ => json_response(200, json!({ "text": text, "id": id })),
+    })
+}
+
+#[derive(Default)]
+struct Form {
+    fields: HashMap<String, String>,
+    /// Whether the `file` part was written to the upload path.
+    has_file: bool,
+}
+
+/// Minimal streaming multipart/form-data parser: text fields are collected,
+/// the `file` part is written to `upload` as it arrives.
+fn parse_multipart(body: &mut impl Read, boundary: &str, upload: &Path) -> Result<Form, HttpError> {
+    let mut parts = Multipart {
+        body,
+        // A leading CRLF lets the first delimiter match like the others
+        buf: b"\r\n".to_vec(),
+        delimiter: format!("\r\n--{}", boundary).into_bytes(),
+    };
+    let mut form = Form::default();
+
+    // Preamble
+    parts.copy_part(&mut std::io::sink(), u64::MAX)?;
+
+    // The closing delimiter is `--boundary--`
+    while parts.take(2)? != b"--" {
+        let headers = parts.read_headers()?;
+        match disposition_param(&headers, "name").as_deref() {
+            Some("file") => {
+                let file = File::create(upload).map_err(|e| (500, e.to_string()))?;
+                let mut writer = BufWriter::new(file);
+                parts.copy_part(&mut writer, u64::MAX)?;
+                writer.flush().map_err(|e| (500, e.to_string()))?;
+                form.has_file = true;
+            }
+            Some(name) => {
+                let mut value = Vec::new();
+                parts.copy_part(&mut value, MAX_FIELD_BYTES)?;
+                form.fields.insert(
+                    name.to_string(),
+                    String::from_utf8_lossy(&value).trim().to_string(),
+                );
+            }
+            None => parts.copy_part(&mut std::io::sink(), u64::MAX)?,
+        }
+    }
+
+    Ok(form)
+}
+
+struct Multipart<'a, R> {
+    body: &'a mut R,
+    /// Read from `body` but not consumed yet.
+    buf: Vec<u8>,
+    delimiter: Vec<u8>,
+}
+
+impl<R: Read> Multipart<'_, R> {
+    /// Reads more of the body; false once it has ended.
+    fn fill(&mut self) -> Result<bool, HttpError> {
+        let mut chunk = vec![0u8; 64 * 1024];
+        let n = self
+            .body
+            .read(&mut chunk)
+            .map_err(|e| (400, e.to_string()))?;
+        self.buf.extend_from_slice(&chunk[..n]);
+        Ok(n > 0)
+    }
+
+    fn take(&mut self, n: usize) -> Result<Vec<u8>, HttpError> {
+        while self.buf.len() < n {
+            if !self.fill()? {
+                return Err(malformed());
+            }
+        }
+        Ok(self.buf.drain(..n).collect())
+    }
+
+    /// The part's headers, after the CRLF that ends the delimiter line.
+    fn read_headers(&mut self) -> Result<String, HttpError> {
+        loop {
+            if let Some(end) = find(&self.buf, b"\r\n\r\n") {
+                let headers = String::from_utf8_lossy(&self.buf[..end]).to_string();
+                self.buf.drain(..end + 4);
+                return Ok(headers);
+            }
+            if self.buf.len() as u64 > MAX_FIELD_BYTES || !self.fill()? {
+                return Err(malformed());
+            }
+        }
+    }
+
+    /// Streams the part's content into `out`, up to and past the next delimiter.
+    fn copy_part(&mut self, out: &mut impl Write, max: u64) -> Result<(), HttpError> {
+        let mut written = 0u64;
+        loop {
+            let found = find(&self.buf, &self.delimiter);
+            // Without a match, hold back a tail that may start a delimiter split across reads
+            let n = found.unwrap_or(self.buf.len().saturating_sub(self.delimiter.len() - 1));
+
+            written += n as u64;
+            if written > max {
+                return Err((400, "Form field too large".into()));
+            }
+            out.write_all(&self.buf[..n])
+                .map_err(|e| (500, e.to_string()))?;
+
+            if found.is_some() {
+                self.buf.drain(..n + self.delimiter.len());
+                return Ok(());
+            }
+            self.buf.drain(..n);
+            if !self.fill()? {
+                return Err(malformed());
+            }
+        }
+    }
+}
+
+fn malformed() -> HttpError {
+    (400, "Malformed multipart body".into())
+}
+
+/// `Content-Disposition: form-data; name="file"; filename="a.mp3"` -> `file` for `name`.
+fn disposition_param(headers: &str, param: &str) -> Option<String> {
+    let line = headers
+        .lines()
+        .find(|l| l.to_ascii_lowercase().starts_with("content-disposition:"))?;
+
+    line.split(';').find_map(|part| {
+        part.trim()
+            .strip_prefix(param)?
+            .strip_prefix('=')
+            .map(|v| v.trim_matches('"').to_string())
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn header_value<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes(&b"Content-Type"[..], value.as_bytes()).unwrap()
+}
+
+fn text_response(body: String, mime: &str) -> Reply {
+    Response::from_string(body).with_header(content_type(mime))
+}
+
+fn json_response(status: u16, value: serde_json::Value) -> Reply {
+    Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(content_type("application/json"))
+}
+
+/// OpenAI-style error body.
+fn error(status: u16, message: &str) -> Reply {
+    json_response(
+        status,
+        json!({ "error": { "message": message, "type": "invalid_request_error" } }),
+    )
+}
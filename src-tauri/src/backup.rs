@@ -0,0 +1,285 @@
+//! History backups, e.g. for moving to a new machine.
+//!
+//! `export` writes a tar archive of `manifest.json` plus
+//! `sessions/<id>/<file>` for each selected session: audio, transcripts,
+//! metadata and hook logs. Files are stored decrypted so the archive imports
+//! on a machine with another key (or none); treat it like the history itself.
+//!
+//! `import` merges an archive into the recordings folder. A session that is
+//! already there (same id and transcript) is skipped; any other id clash is
+//! imported under the next free same-second counter, like `new_session_paths`.
+
+use crate::crypto;
+use crate::state::AppState;
+use crate::types::{HistoryRange, ImportReport};
+use crate::utils::{get_recordings_dir, is_valid_session_id, next_free_session_id};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager};
+
+const MANIFEST: &str = "manifest.json";
+const FORMAT_VERSION: u32 = 1;
+/// Staging folder inside the recordings dir, so sessions can be renamed into place.
+const STAGING_DIR: &str = ".import";
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    app_version: String,
+    exported_at: String,
+    sessions: Vec<String>,
+}
+
+/// Writes the sessions in `range` to a tar archive at `path`.
+/// Returns the number of sessions exported.
+pub async fn export(app: &AppHandle, range: &HistoryRange, path: &Path) -> Result<usize, String> {
+    // Archiving or cleanup must not move files out from under us
+    let state = app.state::<AppState>();
+    let _job = state.transcription_lock.lock().await;
+    let in_use = state.sessions_in_use();
+
+    let recordings_dir = get_recordings_dir(app)?;
+    let mut ids: Vec<String> = fs::read_dir(&recordings_dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|dir| dir.join("transcript.txt").exists() && !in_use.contains(dir))
+        .filter_map(|dir| Some(dir.file_name()?.to_str()?.to_string()))
+        .filter(|id| in_range(range, id))
+        .collect();
+    ids.sort();
+    if ids.is_empty() {
+        return Err("No sessions to export".into());
+    }
+
+    let manifest = Manifest {
+        version: FORMAT_VERSION,
+        app_version: app.package_info().version.to_string(),
+        exported_at: Local::now().to_rfc3339(),
+        sessions: ids,
+    };
+
+    // Written next to the target first, so a failed export never leaves a truncated archive
+    let part = path.with_file_name(format!(
+        "{}.part",
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("history")
+    ));
+    if let Err(e) = write_archive(&recordings_dir, &manifest, &part) {
+        let _ = fs::remove_file(&part);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+    fs::rename(&part, path).map_err(|e| e.to_string())?;
+
+    println!(
+        "[Rust] Exported {} sessions to {}",
+        manifest.sessions.len(),
+        path.display()
+    );
+    Ok(manifest.sessions.len())
+}
+
+fn in_range(range: &HistoryRange, id: &str) -> bool {
+    if !range.ids.is_empty() {
+        return range.ids.iter().any(|wanted| wanted == id);
+    }
+    // Ids start with the session's date, so plain string comparison works
+    let date = id.get(..10).unwrap_or(id);
+    range.from.as_deref().is_none_or(|from| date >= from)
+        && range.to.as_deref().is_none_or(|to| date <= to)
+}
+
+fn write_archive(recordings_dir: &Path, manifest: &Manifest, path: &Path) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut tar = tar::Builder::new(BufWriter::new(file));
+
+    // First entry, so `import` can reject foreign archives before extracting anything
+    let body = serde_json::to_vec_pretty(manifest).map_err(|e| e.to_string())?;
+    append_bytes(&mut tar, MANIFEST, &body, Local::now().timestamp() as u64)?;
+
+    for id in &manifest.sessions {
+        let entries = fs::read_dir(recordings_dir.join(id)).map_err(|e| e.to_string())?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !entry.file_type().is_ok_and(|t| t.is_file()) || !is_session_file(name) {
+                continue;
+            }
+
+            let archive_name = format!("sessions/{}/{}", id, name);
+            if crypto::is_encrypted_file(&path)? {
+                // Keep the mtime: storage cleanup evicts by last use
+                let mtime = entry
+                    .metadata()
+                    .ok()
+                    .and_then(|m| m.modified().ok())
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                append_bytes(&mut tar, &archive_name, &crypto::read(&path)?, mtime)?;
+            } else {
+                tar.append_path_with_name(&path, &archive_name)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    tar.into_inner()
+        .and_then(|mut writer| writer.flush())
+        .map_err(|e| e.to_string())
+}
+
+fn append_bytes<W: Write>(
+    tar: &mut tar::Builder<W>,
+    name: &str,
+    data: &[u8],
+    mtime: u64,
+) -> Result<(), String> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    tar.append_data(&mut header, name, data)
+        .map_err(|e| e.to_string())
+}
+
+/// Plain file names only; leftovers of interrupted writes (`.part`, `.tmp`) are skipped.
+fn is_session_file(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.ends_with(".part")
+        && !name.ends_with(".tmp")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Merges the archive at `path` into the history.
+pub async fn import(app: &AppHandle, path: &Path) -> Result<ImportReport, String> {
+    let state = app.state::<AppState>();
+    let _job = state.transcription_lock.lock().await;
+
+    let recordings_dir = get_recordings_dir(app)?;
+    let staging = recordings_dir.join(STAGING_DIR);
+    // Left over from an import that was interrupted
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging).map_err(|e| e.to_string())?;
+
+    let result = import_staged(path, &recordings_dir, &staging);
+    let _ = fs::remove_dir_all(&staging);
+    let report = result?;
+
+    println!(
+        "[Rust] Imported {} sessions ({} renamed, {} already present)",
+        report.imported,
+        report.renamed.len(),
+        report.duplicates
+    );
+    Ok(report)
+}
+
+fn import_staged(
+    path: &Path,
+    recordings_dir: &Path,
+    staging: &Path,
+) -> Result<ImportReport, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = tar::Archive::new(BufReader::new(file));
+
+    let mut manifest: Option<Manifest> = None;
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let name = entry
+            .path()
+            .map_err(|e| e.to_string())?
+            .to_string_lossy()
+            .to_string();
+
+        if manifest.is_none() {
+            if name != MANIFEST {
+                return Err("Not a Whisper Flow history archive".into());
+            }
+            let mut raw = String::new();
+            entry.read_to_string(&mut raw).map_err(|e| e.to_string())?;
+            let parsed: Manifest = serde_json::from_str(&raw)
+                .map_err(|e| format!("Invalid archive manifest: {}", e))?;
+            if parsed.version > FORMAT_VERSION {
+                return Err("Archive was made by a newer version of Whisper Flow".into());
+            }
+            manifest = Some(parsed);
+            continue;
+        }
+
+        // Archives may come from anywhere: only plain files at known paths are extracted
+        match session_entry(&name) {
+            Some((id, file_name)) if entry.header().entry_type() == tar::EntryType::Regular => {
+                let dir = staging.join(id);
+                fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+                entry
+                    .unpack(dir.join(file_name))
+                    .map_err(|e| e.to_string())?;
+            }
+            _ => println!("[Warn] Skipping archive entry {}", name),
+        }
+    }
+    let manifest = manifest.ok_or("Archive is empty")?;
+
+    let mut report = ImportReport::default();
+    for id in manifest.sessions {
+        let source = staging.join(&id);
+        if !is_valid_session_id(&id) || !source.join("transcript.txt").exists() {
+            println!("[Warn] Archive has no files for session {}", id);
+            continue;
+        }
+
+        let Some(target_id) = resolve_id(recordings_dir, &id, &source) else {
+            report.duplicates += 1;
+            continue;
+        };
+        let target = recordings_dir.join(&target_id);
+        fs::rename(&source, &target).map_err(|e| e.to_string())?;
+        if let Err(e) = crypto::seal_session(&target) {
+            println!("[Warn] Failed to encrypt {}: {}", target_id, e);
+        }
+
+        if target_id != id {
+            report.renamed.insert(id, target_id);
+        }
+        report.imported += 1;
+    }
+    Ok(report)
+}
+
+/// Splits `sessions/<id>/<file>`, rejecting anything else.
+fn session_entry(name: &str) -> Option<(&str, &str)> {
+    let mut parts = name.split('/');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some("sessions"), Some(id), Some(file), None)
+            if is_valid_session_id(id) && is_session_file(file) =>
+        {
+            Some((id, file))
+        }
+        _ => None,
+    }
+}
+
+/// Id to import a session as: `None` if the same session is already there,
+/// otherwise its own id or, when that is taken, the next free same-second counter.
+fn resolve_id(recordings_dir: &Path, id: &str, imported: &Path) -> Option<String> {
+    let existing = recordings_dir.join(id);
+    if existing.exists() {
+        let transcript = crypto::read(&imported.join("transcript.txt")).ok();
+        if transcript.is_some() && crypto::read(&existing.join("transcript.txt")).ok() == transcript
+        {
+            return None;
+        }
+    }
+    Some(next_free_session_id(recordings_dir, id))
+}
@@ -0,0 +1,393 @@
+//! Long-file transcription: cutting the 16 kHz WAV into overlapping chunks at
+//! silences, reporting progress across whisper runs, and stitching the
+//! per-chunk segments back together.
+
+use crate::transcript::Segment;
+use crate::types::TranscriptionProgress;
+use crate::utils::run_sidecar;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+/// Audio shared by neighbouring chunks, so words at a cut are heard whole by one of them.
+pub const CHUNK_OVERLAP_MS: i64 = 2_000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Chunk {
+    /// Audio passed to whisper, overlap included.
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Segments centred in `[keep_from_ms, keep_to_ms)` belong to this chunk;
+    /// the overlap outside it is owned by the neighbours. Internal bounds are
+    /// the cuts, i.e. the midpoints of the overlaps.
+    pub keep_from_ms: i64,
+    pub keep_to_ms: i64,
+}
+
+impl Chunk {
+    pub fn whole(duration_ms: i64) -> Self {
+        Chunk {
+            start_ms: 0,
+            end_ms: duration_ms,
+            keep_from_ms: 0,
+            keep_to_ms: i64::MAX,
+        }
+    }
+
+    pub fn len_ms(&self) -> i64 {
+        self.end_ms - self.start_ms
+    }
+}
+
+/// Duration of a PCM WAV, from the `fmt ` byte rate and the `data` size.
+/// The header isn't always 44 bytes: ffmpeg adds chunks such as `LIST`.
+pub fn wav_duration_ms(path: &Path) -> Result<i64, String> {
+    let invalid = || format!("Not a valid WAV file: {}", path.display());
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let file_len = file.metadata().map_err(|e| e.to_string())?.len();
+
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff).map_err(|_| invalid())?;
+    if &riff[..4] != b"RIFF" || &riff[8..] != b"WAVE" {
+        return Err(invalid());
+    }
+
+    let mut byte_rate = None;
+    loop {
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header).map_err(|_| invalid())?;
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+
+        match &header[..4] {
+            b"data" => {
+                let byte_rate = byte_rate.filter(|&rate| rate > 0).ok_or_else(invalid)?;
+                let start = file.stream_position().map_err(|e| e.to_string())?;
+                let available = file_len.saturating_sub(start);
+                // Streamed WAVs leave the size unset; the audio then runs to the end
+                let size = match size {
+                    0 | 0xFFFF_FFFF => available,
+                    size => size.min(available),
+                };
+                return Ok((size * 1000 / byte_rate) as i64);
+            }
+            b"fmt " if size >= 16 => {
+                let mut fmt = [0u8; 16];
+                file.read_exact(&mut fmt).map_err(|_| invalid())?;
+                byte_rate = Some(u32::from_le_bytes([fmt[8], fmt[9], fmt[10], fmt[11]]) as u64);
+                skip(&mut file, size - 16 + (size & 1))?;
+            }
+            // Chunks are padded to an even size
+            _ => skip(&mut file, size + (size & 1))?,
+        }
+    }
+}
+
+fn skip(file: &mut File, bytes: u64) -> Result<(), String> {
+    file.seek(SeekFrom::Current(bytes as i64))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Midpoints (ms) of the silent stretches ffmpeg's `silencedetect` finds.
+pub async fn detect_silences(app: &AppHandle, wav_path: &Path) -> Result<Vec<i64>, String> {
+    let output = run_sidecar(
+        app,
+        "ffmpeg",
+        [
+            "-hide_banner",
+            "-nostats",
+            "-i",
+            wav_path
+                .to_str()
+                .ok_or_else(|| "Invalid wav path".to_string())?,
+            "-af",
+            "silencedetect=noise=-35dB:d=0.4",
+            "-f",
+            "null",
+            "-",
+        ],
+    )
+    .await?;
+
+    if !output.success {
+        return Err("Silence detection failed or was aborted".into());
+    }
+
+    // [silencedetect @ 0x7f..] silence_start: 12.345
+    // [silencedetect @ 0x7f..] silence_end: 13.21 | silence_duration: 0.865
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut start = None;
+    let mut midpoints = Vec::new();
+
+    for line in stderr.lines() {
+        if let Some(s) = seconds_after(line, "silence_start: ") {
+            start = Some(s);
+        } else if let Some(end) = seconds_after(line, "silence_end: ") {
+            if let Some(s) = start.take() {
+                midpoints.push(((s + end) / 2.0 * 1000.0) as i64);
+            }
+        }
+    }
+
+    Ok(midpoints)
+}
+
+fn seconds_after(line: &str, key: &str) -> Option<f64> {
+    line.split(key)
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Cuts `duration_ms` into chunks of at most `chunk_ms` (plus overlap). Each cut
+/// goes at the latest silence in the last quarter before the limit, or hard at
+/// the limit when there is none.
+pub fn plan_chunks(duration_ms: i64, silences: &[i64], chunk_ms: i64) -> Vec<Chunk> {
+    let mut cuts = vec![0];
+    let mut start = 0;
+
+    while duration_ms - start > chunk_ms {
+        let limit = start + chunk_ms;
+        let earliest = limit - chunk_ms / 4;
+        let cut = silences
+            .iter()
+            .copied()
+            .filter(|&s| s > earliest && s <= limit)
+            .max()
+            .unwrap_or(limit);
+        cuts.push(cut);
+        start = cut;
+    }
+    cuts.push(duration_ms);
+
+    cuts.windows(2)
+        .map(|w| Chunk {
+            start_ms: (w[0] - CHUNK_OVERLAP_MS).max(0),
+            end_ms: (w[1] + CHUNK_OVERLAP_MS).min(duration_ms),
+            keep_from_ms: w[0],
+            keep_to_ms: w[1],
+        })
+        .collect()
+}
+
+/// Moves chunk-relative timestamps to file time.
+pub fn shift_segments(segments: &mut [Segment], offset_ms: i64) {
+    for segment in segments {
+        segment.start_ms += offset_ms;
+        segment.end_ms += offset_ms;
+        for word in &mut segment.words {
+            word.start_ms += offset_ms;
+            word.end_ms += offset_ms;
+        }
+    }
+}
+
+/// Joins per-chunk segments (already in file time). At each seam the earlier
+/// chunk keeps what lies before the overlap midpoint and the later chunk what
+/// lies after it, so a segment heard by both is kept once. A segment belongs to
+/// the side its own midpoint falls on.
+pub fn stitch(chunks: &[Chunk], results: Vec<Vec<Segment>>) -> Vec<Segment> {
+    chunks
+        .iter()
+        .zip(results)
+        .flat_map(|(chunk, segments)| {
+            segments.into_iter().filter(move |segment| {
+                let middle = segment.start_ms + (segment.end_ms - segment.start_ms) / 2;
+                middle >= chunk.keep_from_ms && middle < chunk.keep_to_ms
+            })
+        })
+        .collect()
+}
+
+/// Turns whisper's per-run `progress = N%` into overall `transcription-progress` events.
+pub struct ProgressTracker {
+    app: AppHandle,
+    started: Instant,
+    /// Audio to get through across all runs (chunks x passes).
+    total_ms: i64,
+    done_ms: i64,
+    runs: usize,
+    last_percent: Option<u8>,
+}
+
+impl ProgressTracker {
+    pub fn new(app: &AppHandle, total_ms: i64, runs: usize) -> Self {
+        ProgressTracker {
+            app: app.clone(),
+            started: Instant::now(),
+            total_ms: total_ms.max(1),
+            done_ms: 0,
+            runs,
+            last_percent: None,
+        }
+    }
+
+    /// `run` is 1-based; `run_percent` is whisper's progress within it.
+    pub fn update(&mut self, run: usize, run_ms: i64, run_percent: u32) {
+        let current = self.done_ms as f64 + run_ms as f64 * run_percent.min(100) as f64 / 100.0;
+        let fraction = (current / self.total_ms as f64).clamp(0.0, 1.0);
+        let percent = (fraction * 100.0).round() as u8;
+        if self.last_percent == Some(percent) {
+            return;
+        }
+        self.last_percent = Some(percent);
+
+        // Too early for a meaningful estimate
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let eta_secs = (fraction >= 0.02).then(|| (elapsed / fraction - elapsed).round() as u64);
+
+        let _ = self.app.emit(
+            "transcription-progress",
+            TranscriptionProgress {
+                percent,
+                eta_secs,
+                chunk: run,
+                chunks: self.runs,
+            },
+        );
+    }
+
+    pub fn finish_run(&mut self, run_ms: i64) {
+        self.done_ms += run_ms;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: i64, end_ms: i64, text: &str) -> Segment {
+        Segment {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            speaker: None,
+            words: Vec::new(),
+        }
+    }
+
+    fn texts(segments: &[Segment]) -> Vec<&str> {
+        segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn short_audio_is_one_chunk() {
+        let chunks = plan_chunks(5_000, &[], 10_000);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!((chunks[0].start_ms, chunks[0].end_ms), (0, 5_000));
+    }
+
+    #[test]
+    fn chunks_are_cut_at_a_late_silence() {
+        // 8.5 s is in the last quarter before the 10 s limit; 3 s is not
+        let chunks = plan_chunks(20_000, &[3_000, 8_500], 10_000);
+        let cuts: Vec<_> = chunks
+            .iter()
+            .map(|c| (c.keep_from_ms, c.keep_to_ms))
+            .collect();
+        assert_eq!(cuts, [(0, 8_500), (8_500, 18_500), (18_500, 20_000)]);
+
+        assert_eq!((chunks[0].start_ms, chunks[0].end_ms), (0, 10_500));
+        assert_eq!((chunks[1].start_ms, chunks[1].end_ms), (6_500, 20_000));
+        assert_eq!((chunks[2].start_ms, chunks[2].end_ms), (16_500, 20_000));
+    }
+
+    #[test]
+    fn chunks_are_cut_hard_without_silence() {
+        let chunks = plan_chunks(25_000, &[], 10_000);
+        let cuts: Vec<_> = chunks
+            .iter()
+            .map(|c| (c.keep_from_ms, c.keep_to_ms))
+            .collect();
+        assert_eq!(cuts, [(0, 10_000), (10_000, 20_000), (20_000, 25_000)]);
+    }
+
+    #[test]
+    fn stitch_keeps_each_side_of_the_seam_once() {
+        let chunks = plan_chunks(20_000, &[], 10_000);
+        let first = vec![
+            segment(0, 4_000, "one"),
+            segment(8_500, 10_500, "two"),
+            segment(10_500, 12_000, "three (first run)"),
+        ];
+        let second = vec![
+            segment(8_000, 10_400, "two (second run)"),
+            segment(10_500, 12_000, "three"),
+            segment(15_000, 20_000, "four"),
+        ];
+
+        let stitched = stitch(&chunks, vec![first, second]);
+        assert_eq!(texts(&stitched), ["one", "two", "three", "four"]);
+    }
+
+    #[test]
+    fn stitch_keeps_repeated_words_on_both_sides() {
+        // Same text on both sides of the seam is real speech, not a duplicate
+        let chunks = plan_chunks(20_000, &[], 10_000);
+        let first = vec![segment(8_000, 9_000, "yes")];
+        let second = vec![segment(8_000, 9_000, "yes"), segment(11_000, 12_000, "yes")];
+
+        let stitched = stitch(&chunks, vec![first, second]);
+        assert_eq!(texts(&stitched), ["yes", "yes"]);
+        assert_eq!(stitched[1].start_ms, 11_000);
+    }
+
+    fn write_wav(name: &str, extra_chunk: bool, data_len: u32) -> std::path::PathBuf {
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        // fmt: PCM, mono, 16 kHz, 16-bit
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&16_000u32.to_le_bytes());
+        bytes.extend_from_slice(&32_000u32.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        if extra_chunk {
+            // Odd size, so the padding byte has to be skipped too
+            bytes.extend_from_slice(b"LIST");
+            bytes.extend_from_slice(&5u32.to_le_bytes());
+            bytes.extend_from_slice(b"INFO\0\0");
+        }
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        bytes.resize(bytes.len() + 96_000, 0);
+
+        let path = std::env::temp_dir().join(format!("{}-{}.wav", name, std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn wav_duration_reads_the_data_chunk() {
+        let path = write_wav("plain", false, 96_000);
+        assert_eq!(wav_duration_ms(&path), Ok(3_000));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wav_duration_skips_extra_chunks() {
+        let path = write_wav("list", true, 96_000);
+        assert_eq!(wav_duration_ms(&path), Ok(3_000));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wav_duration_of_a_streamed_wav_runs_to_the_end() {
+        let path = write_wav("streamed", false, 0xFFFF_FFFF);
+        assert_eq!(wav_duration_ms(&path), Ok(3_000));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wav_duration_rejects_other_files() {
+        let path = std::env::temp_dir().join(format!("not-a-wav-{}.wav", std::process::id()));
+        std::fs::write(&path, b"ID3 not a wav at all").unwrap();
+        assert!(wav_duration_ms(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
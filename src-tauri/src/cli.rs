@@ -0,0 +1,160 @@
+//! Headless command-line transcription:
+//!
+//!     whisper-flow transcribe [options] -o <OUTPUT> <FILE | URL | ->
+//!
+//! `-` reads the media from stdin; add `--pcm` when it is headerless 16 kHz mono
+//! s16le (`arecord -f S16_LE -r 16000 -c 1 | whisper-flow transcribe --pcm -`).
+//! Options that aren't given fall back to the app's saved settings. The app
+//! runs without showing a window and exits once the transcript is written.
+//! The transcript always goes to `--output`: the app logs to stdout.
+
+use crate::commands::audio::transcribe_file;
+use crate::source::MediaSource;
+use crate::state::AppState;
+use crate::types::{AudioChannel, FileTranscribeOptions, MediaSlice, OutputMode, WhisperTask};
+use std::io::Read;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+pub const USAGE: &str = "Usage: whisper-flow transcribe [options] -o <OUTPUT> <FILE | URL | ->
+
+Options:
+  -m, --model <NAME>        Whisper model (e.g. large-v3-turbo)
+  -l, --language <CODE>     Language code or `auto`
+      --prompt <TEXT>       Initial prompt
+      --task <TASK>         transcribe | translate | both
+      --timestamps          Output SRT
+      --start <SECS>        Start of the range to transcribe
+      --end <SECS>          End of the range to transcribe
+      --stream <N>          Audio stream index
+      --channel <CH>        mix | left | right
+      --pcm                 stdin is raw 16 kHz mono s16le PCM
+  -o, --output <FILE>       Write the transcript here (required; stdout
+                            carries diagnostic logs)";
+
+pub struct CliJob {
+    pub source: MediaSource,
+    pub model_type: Option<String>,
+    pub language: Option<String>,
+    pub prompt: Option<String>,
+    pub task: Option<WhisperTask>,
+    pub with_timestamps: bool,
+    pub slice: MediaSlice,
+    pub output: PathBuf,
+}
+
+/// `None` when the app was launched normally, i.e. without `transcribe`.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Option<Result<CliJob, String>> {
+    let mut args = args.into_iter();
+    if args.next().as_deref() != Some("transcribe") {
+        return None;
+    }
+    Some(parse_transcribe(args))
+}
+
+fn parse_transcribe(mut args: impl Iterator<Item = String>) -> Result<CliJob, String> {
+    let mut input = None;
+    let mut output = None;
+    let mut pcm = false;
+    let mut job = CliJob {
+        source: MediaSource::Bytes(vec![]),
+        model_type: None,
+        language: None,
+        prompt: None,
+        task: None,
+        with_timestamps: false,
+        slice: MediaSlice::default(),
+        output: PathBuf::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "-m" | "--model" => job.model_type = Some(value()?),
+            "-l" | "--language" => job.language = Some(value()?),
+            "--prompt" => job.prompt = Some(value()?),
+            "--task" => job.task = Some(parse_enum(&value()?)?),
+            "--timestamps" => job.with_timestamps = true,
+            "--start" => job.slice.start_secs = Some(parse_number(&value()?)?),
+            "--end" => job.slice.end_secs = Some(parse_number(&value()?)?),
+            "--stream" => job.slice.audio_stream = Some(parse_number(&value()?)?),
+            "--channel" => job.slice.channel = parse_enum::<AudioChannel>(&value()?)?,
+            "--pcm" => pcm = true,
+            "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+            "-" => input = Some(arg),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            _ => input = Some(arg),
+        }
+    }
+
+    job.output = output.ok_or("No --output given")?;
+    job.source = match input.as_deref() {
+        None => return Err("No input given".into()),
+        Some("-") => {
+            let mut data = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut data)
+                .map_err(|e| format!("Failed to read stdin: {}", e))?;
+            if pcm {
+                MediaSource::RawPcm(data)
+            } else {
+                MediaSource::Bytes(data)
+            }
+        }
+        Some(path) => MediaSource::from_input(path),
+    };
+
+    Ok(job)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid number: {}", value))
+}
+
+/// Parses a snake_case enum value the same way the IPC layer does.
+fn parse_enum<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("Invalid value: {}", value))
+}
+
+/// Runs `job` in the background and exits the app with 0 on success, 1 on failure.
+pub fn run(app: AppHandle, job: CliJob) {
+    for window in app.webview_windows().values() {
+        let _ = window.hide();
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+        let options = FileTranscribeOptions {
+            model_type: job.model_type.unwrap_or(settings.model_type),
+            language: job.language.unwrap_or(settings.language),
+            prompt: job.prompt.unwrap_or(settings.prompt),
+            task: job.task.unwrap_or(settings.task),
+            with_timestamps: job.with_timestamps,
+            diarize: false,
+            max_chars: None,
+            slice: job.slice,
+        };
+
+        let result = transcribe_file(&app, job.source, &options, OutputMode::None)
+            .await
+            .and_then(|text| {
+                std::fs::write(&job.output, &text)
+                    .map_err(|e| format!("Failed to write {}: {}", job.output.display(), e))
+            });
+
+        let code = match result {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                1
+            }
+        };
+        app.exit(code);
+    });
+}
@@ -0,0 +1,99 @@
+//! Snapshot / restore of the general pasteboard around auto-paste.
+//!
+//! `clipboard().write_text` replaces every representation on the pasteboard
+//! (images, RTF, file URLs...). We copy each item's raw data per type before
+//! writing the transcript, and put it back once the simulated Cmd+V has landed.
+
+use objc2::rc::{autoreleasepool, Retained};
+use objc2::runtime::ProtocolObject;
+use objc2_app_kit::{NSPasteboard, NSPasteboardItem, NSPasteboardWriting};
+use objc2_foundation::{NSArray, NSData, NSString};
+
+/// One pasteboard item: every (UTI type, raw bytes) pair it carried.
+type PasteboardItem = Vec<(String, Vec<u8>)>;
+
+pub struct ClipboardSnapshot {
+    items: Vec<PasteboardItem>,
+}
+
+/// Current pasteboard change counter. It increments on every write by any app,
+/// so comparing two readings tells us whether someone touched the clipboard.
+pub fn change_count() -> isize {
+    unsafe { NSPasteboard::generalPasteboard().changeCount() }
+}
+
+/// Copies all items and representations currently on the general pasteboard.
+pub fn snapshot() -> ClipboardSnapshot {
+    autoreleasepool(|_| unsafe {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        let items = match pasteboard.pasteboardItems() {
+            Some(items) => items,
+            None => return ClipboardSnapshot { items: vec![] },
+        };
+
+        let items = items
+            .iter()
+            .map(|item| {
+                item.types()
+                    .iter()
+                    .filter_map(|ty| {
+                        item.dataForType(&ty)
+                            .map(|data| (ty.to_string(), data.to_vec()))
+                    })
+                    .collect::<PasteboardItem>()
+            })
+            .filter(|item| !item.is_empty())
+            .collect();
+
+        ClipboardSnapshot { items }
+    })
+}
+
+/// Writes the snapshot back. An empty snapshot clears the pasteboard, matching
+/// what the user had before we wrote the transcript.
+pub fn restore(snapshot: ClipboardSnapshot) {
+    autoreleasepool(|_| unsafe {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        pasteboard.clearContents();
+
+        if snapshot.items.is_empty() {
+            return;
+        }
+
+        let objects: Vec<Retained<ProtocolObject<dyn NSPasteboardWriting>>> = snapshot
+            .items
+            .into_iter()
+            .map(|entries| {
+                let item = NSPasteboardItem::new();
+                for (ty, bytes) in entries {
+                    let data = NSData::with_bytes(&bytes);
+                    item.setData_forType(&data, &NSString::from_str(&ty));
+                }
+                ProtocolObject::from_retained(item)
+            })
+            .collect();
+
+        pasteboard.writeObjects(&NSArray::from_retained_slice(&objects));
+    })
+}
+
+/// Restores `snapshot` unless the pasteboard changed after our own write
+/// (`expected_change_count`), e.g. because the user copied something else.
+pub fn restore_if_unchanged(snapshot: ClipboardSnapshot, expected_change_count: isize) {
+    if change_count() != expected_change_count {
+        println!("[Rust] Clipboard changed since auto-paste, skipping restore");
+        return;
+    }
+
+    restore(snapshot);
+    println!("[Rust] Clipboard restored");
+}
+
+/// The first file URL on the pasteboard (e.g. a file copied in Finder), as a `file://` string.
+pub fn file_url() -> Option<String> {
+    autoreleasepool(|_| unsafe {
+        NSPasteboard::generalPasteboard()
+            .stringForType(&NSString::from_str("public.file-url"))
+            .map(|url| url.to_string())
+    })
+}
@@ -1,9 +1,36 @@
+use crate::chunking::{
+    detect_silences, plan_chunks, shift_segments, stitch, wav_duration_ms, Chunk, ProgressTracker,
+};
+#[cfg(target_os = "macos")]
+use crate::clipboard;
+use crate::consts::DIARIZATION_MODEL;
+use crate::corrections::prompt_with_vocabulary;
+use crate::crypto;
+use crate::hooks::spawn_hooks;
+use crate::journal;
+use crate::profiles::{active_profile_options, apply_post_processing, frontmost_app_options};
+use crate::session::{
+    load_meta, save_meta, save_segments, SessionMeta, SEGMENTS_FILE, TRANSLATION_FILE,
+};
+use crate::settings::modify_settings;
+use crate::source::{MediaSource, PreparedInput};
 use crate::state::AppState;
-use crate::types::{AudioDevice, RecordingSession};
-use crate::utils::{get_model_info, interrupt_and_wait, new_session_paths, simulate_paste};
+use crate::storage;
+use crate::transcript::{split_segments, to_srt, to_text, Segment};
+use crate::types::{
+    AudioChannel, AudioDevice, DictationOptions, FileTranscribeOptions, MediaSlice, OutputMode,
+    RecordingSession, WhisperTask,
+};
+use crate::utils::{
+    existing_session_dir, get_model_info, interrupt_and_wait, new_session_paths, resolve_model,
+    run_sidecar, simulate_paste,
+};
+use crate::whisper::{resolve_language, run_whisper};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::thread;
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_shell::process::CommandEvent;
 use tauri_plugin_shell::ShellExt;
@@ -93,14 +120,33 @@ pub async fn start_recording(
     state: State<'_, AppState>,
     device_id: String,
 ) -> Result<String, String> {
-    // Prevent double-trigger / re-entrancy
+    // Remember the device so backend-driven recordings (push-to-talk) use the same one
+    modify_settings(&app, |s| s.device_id = device_id.clone())?;
+
+    // A toggle shortcut bound to a profile leaves its options here for us
+    let options = state.pending_options.lock().unwrap().take();
+
+    begin_recording(&app, &device_id, options.unwrap_or_default())
+}
+
+/// Spawns the recording ffmpeg for a new session.
+/// Shared by the `start_recording` command and the global shortcut handler.
+pub fn begin_recording(
+    app: &AppHandle,
+    device_id: &str,
+    options: DictationOptions,
+) -> Result<String, String> {
+    let state = app.state::<AppState>();
+
+    // Prevent double-trigger / re-entrancy. An error, so automation never
+    // mistakes this for the id of a new session
     let mut guard = state.session.lock().unwrap();
     if guard.is_some() {
         println!("[Rust] Recording already active. Ignoring start request.");
-        return Ok("Already Recording".into());
+        return Err("Already Recording".into());
     }
 
-    let (session_id, session_dir, raw_path, _, transcript_path) = new_session_paths(&app)?;
+    let (session_id, session_dir, raw_path, _, transcript_path) = new_session_paths(app)?;
 
     println!(
         "--- [Debug] Start Recording (session: {}, device: {}) ---",
@@ -185,6 +231,7 @@ pub async fn start_recording(
         wav_path: transcript_path.with_file_name("input_16k.wav"), // reconstruct for consistency
         transcript_path,
         child,
+        options,
     });
 
     Ok(session_id)
@@ -196,23 +243,66 @@ pub async fn start_recording(
 #[tauri::command(rename_all = "camelCase")]
 pub async fn stop_and_transcribe(
     app: AppHandle,
-    state: State<'_, AppState>,
     model_type: String,
     language: String,
     prompt: String,
+    task: Option<WhisperTask>,
+) -> Result<String, String> {
+    let mut task_to_use = WhisperTask::default();
+    modify_settings(&app, |s| {
+        s.model_type = model_type.clone();
+        s.language = language.clone();
+        s.prompt = prompt.clone();
+        if let Some(task) = task {
+            s.task = task;
+        }
+        task_to_use = s.task;
+    })?;
+
+    finish_recording(&app, &model_type, &language, &prompt, task_to_use).await
+}
+
+/// Stops the active recording and transcribes it.
+/// Shared by the `stop_and_transcribe` command and the global shortcut handler.
+pub async fn finish_recording(
+    app: &AppHandle,
+    model_type: &str,
+    language: &str,
+    prompt: &str,
+    task: WhisperTask,
 ) -> Result<String, String> {
-    println!("--- [Debug] Stop requested (Lang: {}) ---", language);
+    let state = app.state::<AppState>();
 
-    // Take session atomically (releases the lock quickly)
+    // Options a shortcut left for a start that never came don't carry over
+    state.pending_options.lock().unwrap().take();
+
+    // Take session atomically, marking its folder as in use until the job runs
     let session = {
         let mut guard = state.session.lock().unwrap();
-        guard.take()
+        let session = guard.take().ok_or("No active recording session")?;
+        state
+            .queued_dictations
+            .lock()
+            .unwrap()
+            .push(session.dir.clone());
+        session
     };
 
-    let session = match session {
-        Some(s) => s,
-        None => return Err("No active recording session".into()),
-    };
+    // Precedence: caller (UI) options < active profile < per-app rule for the
+    // frontmost app < options attached to the session (e.g. by the shortcut binding).
+    // Resolved now: the user may switch apps while the job waits for its turn
+    let options = active_profile_options(app)
+        .merged_with(&frontmost_app_options(app))
+        .merged_with(&session.options);
+    let model_type = options.model_type.as_deref().unwrap_or(model_type);
+    let language = options.language.as_deref().unwrap_or(language);
+    let prompt = prompt_with_vocabulary(app, options.prompt.as_deref().unwrap_or(prompt));
+    let task = options.task.unwrap_or(task);
+
+    println!(
+        "--- [Debug] Stop requested (Lang: {}, Task: {:?}) ---",
+        language, task
+    );
 
     let pid = session.child.pid();
     println!("Sending SIGINT to FFmpeg PID: {}...", pid);
@@ -220,6 +310,15 @@ pub async fn stop_and_transcribe(
     // Stop gracefully and wait until it actually exits
     interrupt_and_wait(pid, 3000).await;
 
+    // Queue behind file jobs like any other transcription. A new recording can
+    // start meanwhile; holding the lock keeps other jobs off this folder from here on.
+    let _job = state.transcription_lock.lock().await;
+    state
+        .queued_dictations
+        .lock()
+        .unwrap()
+        .retain(|dir| *dir != session.dir);
+
     // Validate raw output
     if !session.raw_path.exists() {
         return Err(format!(
@@ -240,159 +339,128 @@ pub async fn stop_and_transcribe(
 
     // Convert to WAV
     println!("Converting to WAV: {}", session.wav_path.display());
-    let mut convert_rx = {
-        let (rx, child) = app
-            .shell()
-            .sidecar("ffmpeg")
-            .map_err(|e| e.to_string())?
-            .args([
-                "-y",
-                "-f",
-                "s16le",
-                "-ar",
-                "16000",
-                "-ac",
-                "1",
-                "-i",
-                session
-                    .raw_path
-                    .to_str()
-                    .ok_or_else(|| "Invalid raw path".to_string())?,
-                session
-                    .wav_path
-                    .to_str()
-                    .ok_or_else(|| "Invalid wav path".to_string())?,
-            ])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-
-        // Store child for abortion
-        let mut guard = state.processing_child.lock().unwrap();
-        *guard = Some(child);
-        rx
-    };
-
-    let mut convert_stderr = Vec::new();
-    let mut convert_success = false;
-    while let Some(event) = convert_rx.recv().await {
-        match event {
-            CommandEvent::Stderr(line) => convert_stderr.extend(line),
-            CommandEvent::Terminated(payload) => {
-                convert_success = payload.code == Some(0);
-                break;
-            }
-            _ => {}
-        }
-    }
-
-    // Clear child
-    {
-        let mut guard = state.processing_child.lock().unwrap();
-        *guard = None;
-    }
+    let convert = run_sidecar(
+        app,
+        "ffmpeg",
+        [
+            "-y",
+            "-f",
+            "s16le",
+            "-ar",
+            "16000",
+            "-ac",
+            "1",
+            "-i",
+            session
+                .raw_path
+                .to_str()
+                .ok_or_else(|| "Invalid raw path".to_string())?,
+            session
+                .wav_path
+                .to_str()
+                .ok_or_else(|| "Invalid wav path".to_string())?,
+        ],
+    )
+    .await?;
 
-    if !convert_success {
-        let stderr_str = String::from_utf8_lossy(&convert_stderr);
+    if !convert.success {
+        let stderr_str = String::from_utf8_lossy(&convert.stderr);
         return Err(format!("FFmpeg conversion failed: {}", stderr_str));
     }
+    storage::after_conversion(app, &session.raw_path);
 
     // Run Whisper
     println!("Running Whisper...");
-    let (model_path, _) = get_model_info(&app, &model_type)?;
-
-    let mut whisper_rx = {
-        let (rx, child) = app
-            .shell()
-            .sidecar("whisper-cli")
-            .map_err(|e| e.to_string())?
-            .args([
-                "-m",
-                model_path
-                    .to_str()
-                    .ok_or_else(|| "Invalid model path".to_string())?,
-                "-f",
-                session
-                    .wav_path
-                    .to_str()
-                    .ok_or_else(|| "Invalid wav path".to_string())?,
-                "-t",
-                "8",
-                "-l",
-                &language,
-                "-nt",
-                "--prompt",
-                &prompt,
-            ])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-
-        let mut guard = state.processing_child.lock().unwrap();
-        *guard = Some(child);
-        rx
+    let (model_path, _) = get_model_info(app, model_type)?;
+    let resolved = resolve_language(app, &model_path, &session.wav_path, language).await?;
+
+    let mut meta = SessionMeta {
+        task,
+        ..Default::default()
     };
+    resolved.record_in(&mut meta);
+    save_meta(&session.dir, &meta)?;
+
+    let whisper_args = vec![
+        "-m",
+        model_path
+            .to_str()
+            .ok_or_else(|| "Invalid model path".to_string())?,
+        "-f",
+        session
+            .wav_path
+            .to_str()
+            .ok_or_else(|| "Invalid wav path".to_string())?,
+        "-t",
+        "8",
+        "-l",
+        resolved.language.as_str(),
+        "-nt",
+        "--prompt",
+        prompt.as_str(),
+    ];
 
-    let mut whisper_stdout = Vec::new();
-    let mut whisper_stderr = Vec::new();
-    let mut whisper_success = false;
-    while let Some(event) = whisper_rx.recv().await {
-        match event {
-            CommandEvent::Stdout(line) => whisper_stdout.extend(line),
-            CommandEvent::Stderr(line) => whisper_stderr.extend(line),
-            CommandEvent::Terminated(payload) => {
-                whisper_success = payload.code == Some(0);
-                break;
-            }
-            _ => {}
+    // One run per requested output: original first, then the translation
+    let mut texts = Vec::new();
+    let mut whisper_stderr = String::new();
+    for &translate in task.passes() {
+        let mut args = whisper_args.clone();
+        if translate {
+            args.push("--translate");
         }
-    }
 
-    // Clear child
-    {
-        let mut guard = state.processing_child.lock().unwrap();
-        *guard = None;
-    }
+        let whisper = run_sidecar(app, "whisper-cli", args).await?;
+        if !whisper.success {
+            return Err("Whisper transcription failed or was aborted".into());
+        }
 
-    if !whisper_success {
-        return Err("Whisper transcription failed or was aborted".into());
+        let mut text = String::from_utf8_lossy(&whisper.stdout).trim().to_string();
+        if let Some(rules) = &options.post_processing {
+            text = apply_post_processing(&text, rules);
+        }
+        texts.push(text);
+        whisper_stderr = String::from_utf8_lossy(&whisper.stderr).to_string();
     }
 
-    let transcript_text = String::from_utf8_lossy(&whisper_stdout).trim().to_string();
-    let whisper_stderr = String::from_utf8_lossy(&whisper_stderr).to_string();
-
-    // Persist transcript.txt
-    let transcript_body = if !transcript_text.is_empty() {
-        transcript_text.clone()
+    // Persist transcript.txt (and translation.txt when both were requested)
+    let transcript_body = if !texts[0].is_empty() {
+        texts[0].clone()
     } else {
         format!("(empty)\n\nstderr:\n{}", whisper_stderr)
     };
 
-    tokio::fs::write(&session.transcript_path, transcript_body)
-        .await
+    crypto::write(&session.transcript_path, transcript_body)
         .map_err(|e| format!("Failed to write transcript: {}", e))?;
 
+    if let Some(translation) = texts.get(1) {
+        crypto::write(&session.dir.join(TRANSLATION_FILE), translation)
+            .map_err(|e| format!("Failed to write translation: {}", e))?;
+    }
+
+    // The translation is what gets delivered when one was requested
+    let transcript_text = texts.pop().unwrap_or_default();
+
     // Optional: completion sounds
     if !transcript_text.is_empty() {
         let _ = app.emit("transcription-result", &transcript_text);
+        *state.last_transcript.lock().unwrap() = Some(transcript_text.clone());
 
-        // Auto-Copy (Backend is more reliable than Frontend writeText)
-        let _ = app.clipboard().write_text(transcript_text.clone());
-
+        // 1. Play Succcess Sound (Fire and forget)
         #[cfg(target_os = "macos")]
-        {
-            // 1. Play Succcess Sound (Fire and forget)
-            thread::spawn(|| {
-                let _ = Command::new("afplay")
-                    .arg("/System/Library/Sounds/Blow.aiff")
-                    .output();
-            });
+        thread::spawn(|| {
+            let _ = Command::new("afplay")
+                .arg("/System/Library/Sounds/Blow.aiff")
+                .output();
+        });
 
-            // 2. Auto-Paste: Simulate Cmd+V
-            // Small delay to ensure clipboard is ready
-            thread::spawn(|| {
-                thread::sleep(std::time::Duration::from_millis(50));
-                simulate_paste();
-            });
-        }
+        // 2. Copy / paste according to the session's output mode
+        deliver_transcript(
+            app,
+            &transcript_text,
+            options.output_mode.unwrap_or(OutputMode::Paste),
+        );
+
+        spawn_hooks(app, &session.id, "dictation", &transcript_text);
     } else {
         thread::spawn(|| {
             let _ = Command::new("afplay")
@@ -401,187 +469,572 @@ pub async fn stop_and_transcribe(
         });
     }
 
+    storage::spawn_archive(app, session.dir);
     Ok(transcript_text)
 }
 
+/// Hands a finished transcript to the user: `Copy` puts it on the clipboard,
+/// `Paste` additionally simulates Cmd+V and (optionally) restores the previous
+/// clipboard afterwards, `None` leaves the clipboard alone, `Journal` appends
+/// to the day's Markdown note instead.
+pub fn deliver_transcript(app: &AppHandle, text: &str, mode: OutputMode) {
+    match mode {
+        OutputMode::None => return,
+        OutputMode::Journal => {
+            // May wait out an editor holding the note
+            let (app, text) = (app.clone(), text.to_string());
+            thread::spawn(move || {
+                if let Err(e) = journal::append(&app, &text) {
+                    println!("[Warn] {}", e);
+                    let _ = app.emit("transcription-error", e);
+                }
+            });
+            return;
+        }
+        OutputMode::Paste | OutputMode::Copy => {}
+    }
+
+    // Only the macOS paste path reads the settings
+    #[cfg(target_os = "macos")]
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+
+    // Snapshot whatever the user had copied before we overwrite it
+    #[cfg(target_os = "macos")]
+    let previous_clipboard =
+        (mode == OutputMode::Paste && settings.restore_clipboard).then(clipboard::snapshot);
+
+    // Auto-Copy (Backend is more reliable than Frontend writeText)
+    let _ = app.clipboard().write_text(text.to_string());
+
+    #[cfg(target_os = "macos")]
+    if mode == OutputMode::Paste {
+        // Auto-Paste: Simulate Cmd+V
+        // Small delay to ensure clipboard is ready
+        let written_change_count = clipboard::change_count();
+        let restore_delay = settings.clipboard_restore_delay_ms;
+        thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(50));
+            simulate_paste();
+
+            // Restore the previous clipboard once the paste has landed
+            if let Some(snapshot) = previous_clipboard {
+                thread::sleep(std::time::Duration::from_millis(restore_delay));
+                clipboard::restore_if_unchanged(snapshot, written_change_count);
+            }
+        });
+    }
+}
+
+/// Stops the active recording without transcribing and removes its session folder.
+pub async fn discard_recording(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.pending_options.lock().unwrap().take();
+
+    let session = {
+        let mut guard = state.session.lock().unwrap();
+        guard.take()
+    };
+
+    let session = match session {
+        Some(s) => s,
+        None => return Ok(()),
+    };
+
+    println!(
+        "--- [Debug] Discarding recording (session: {}) ---",
+        session.id
+    );
+    interrupt_and_wait(session.child.pid(), 3000).await;
+
+    tokio::fs::remove_dir_all(&session.dir)
+        .await
+        .map_err(|e| format!("Failed to remove session folder: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_recording(app: AppHandle) -> Result<(), String> {
+    discard_recording(&app).await
+}
+
+/// `diarize` labels speakers with tinydiarize (requires the `small.en-tdrz` model).
+/// `max_chars` splits SRT cues at word boundaries.
+/// `task` selects transcription, English translation, or both.
+/// `slice` limits it to a time range, audio stream or channel of the file.
 #[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)]
 pub async fn transcribe_external_file(
     app: AppHandle,
-    state: State<'_, AppState>,
     file_path: String,
     model_type: String,
     language: String,
     with_timestamps: bool,
     prompt: String,
+    diarize: Option<bool>,
+    max_chars: Option<usize>,
+    task: Option<WhisperTask>,
+    slice: Option<MediaSlice>,
 ) -> Result<String, String> {
+    modify_settings(&app, |s| s.with_timestamps = with_timestamps)?;
+
+    let options = FileTranscribeOptions {
+        model_type,
+        language,
+        prompt,
+        task: task.unwrap_or_default(),
+        with_timestamps,
+        diarize: diarize.unwrap_or(false),
+        max_chars,
+        slice: slice.unwrap_or_default(),
+    };
+
+    transcribe_file(
+        &app,
+        MediaSource::from_input(&file_path),
+        &options,
+        OutputMode::Copy,
+    )
+    .await
+}
+
+/// Transcribes media sent from the frontend as bytes (e.g. audio dragged out of
+/// a browser), in any container ffmpeg understands.
+#[tauri::command]
+pub async fn transcribe_bytes(
+    app: AppHandle,
+    data: Vec<u8>,
+    options: FileTranscribeOptions,
+) -> Result<String, String> {
+    transcribe_file(&app, MediaSource::Bytes(data), &options, OutputMode::Copy).await
+}
+
+/// Converts any media (file, URL, bytes) to 16 kHz WAV in a new session folder,
+/// transcribes it and delivers the result. Shared by the transcription commands,
+/// the global shortcut handler and the CLI.
+pub async fn transcribe_file(
+    app: &AppHandle,
+    source: MediaSource,
+    options: &FileTranscribeOptions,
+    output_mode: OutputMode,
+) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let (session_id, final_text) = transcribe_media(app, source, options).await?;
+
+    // Auto-copy
+    if !final_text.is_empty() {
+        *state.last_transcript.lock().unwrap() = Some(final_text.clone());
+        deliver_transcript(app, &final_text, output_mode);
+        spawn_hooks(app, &session_id, "file", &final_text);
+    }
+
+    Ok(final_text)
+}
+
+/// Core of `transcribe_file` without delivery. Returns `(session id, text)`.
+///
+/// Jobs run one at a time: sidecars share the single `processing_child` slot,
+/// so concurrent callers (IPC, shortcuts, HTTP API) wait their turn.
+pub async fn transcribe_media(
+    app: &AppHandle,
+    source: MediaSource,
+    options: &FileTranscribeOptions,
+) -> Result<(String, String), String> {
+    let state = app.state::<AppState>();
+    let _job = state.transcription_lock.lock().await;
+
     println!(
-        "--- [Debug] Processing external file: {} (Lang: {}, Timestamps: {}) ---",
-        file_path, language, with_timestamps
+        "--- [Debug] Processing external file: {} (Lang: {}, Task: {:?}, Timestamps: {}, Diarize: {}) ---",
+        source.describe(), options.language, options.task, options.with_timestamps, options.diarize
     );
 
+    // Fail on a missing diarization model before doing any work
+    transcription_model(app, options)?;
+
     // 1. Create a new Session folder
-    let (_, _, _, wav_path, transcript_path) = new_session_paths(&app)?;
-
-    // 2. Use FFmpeg to convert input (MP4/MP3/etc) to 16kHz WAV
-    println!("Converting to WAV...");
-    let mut convert_rx = {
-        let (rx, child) = app
-            .shell()
-            .sidecar("ffmpeg")
-            .map_err(|e| e.to_string())?
-            .args([
-                "-y",
-                "-i",
-                &file_path,
-                "-vn",
-                "-ar",
-                "16000",
-                "-ac",
-                "1",
-                wav_path
-                    .to_str()
-                    .ok_or_else(|| "Invalid wav path".to_string())?,
-            ])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-
-        let mut guard = state.processing_child.lock().unwrap();
-        *guard = Some(child);
-        rx
+    let (session_id, session_dir, _, wav_path, _) = new_session_paths(app)?;
+
+    let result = async {
+        // 2. Use FFmpeg to convert input (MP4/MP3/etc) to 16kHz WAV
+        let input = source.prepare(&session_dir).await?;
+        println!("Converting to WAV...");
+        let convert = run_sidecar(
+            app,
+            "ffmpeg",
+            conversion_args(&input, &wav_path, &options.slice)?,
+        )
+        .await?;
+
+        if let Some(temp_file) = &input.temp_file {
+            let _ = tokio::fs::remove_file(temp_file).await;
+        }
+
+        if !convert.success {
+            let stderr_str = String::from_utf8_lossy(&convert.stderr);
+            return Err(format!("FFmpeg conversion failed: {}", stderr_str));
+        }
+
+        // 3. Run Whisper and persist the results
+        transcribe_session(app, &session_dir, &wav_path, options).await
+    }
+    .await;
+
+    match result {
+        Ok(text) => {
+            storage::spawn_archive(app, session_dir);
+            Ok((session_id, text))
+        }
+        Err(e) => {
+            // Don't leave a half-made session in the history
+            let _ = tokio::fs::remove_dir_all(&session_dir).await;
+            Err(e)
+        }
+    }
+}
+
+/// Runs an existing session's audio through whisper again, e.g. with another
+/// model, language or task. Timestamp, diarization and cue-length choices are
+/// kept from the original run. Returns the new text without delivering it.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn retranscribe_history_item(
+    app: AppHandle,
+    id: String,
+    model_type: String,
+    language: String,
+    prompt: String,
+    task: Option<WhisperTask>,
+) -> Result<String, String> {
+    let session_dir = existing_session_dir(&app, &id)?;
+    let meta = load_meta(&session_dir);
+    let options = FileTranscribeOptions {
+        model_type,
+        language,
+        prompt,
+        task: task.unwrap_or(meta.task),
+        with_timestamps: meta.with_timestamps,
+        diarize: meta.diarized,
+        max_chars: meta.max_chars,
+        slice: meta.slice,
     };
 
-    let mut convert_stderr = Vec::new();
-    let mut convert_success = false;
-    while let Some(event) = convert_rx.recv().await {
-        match event {
-            CommandEvent::Stderr(line) => convert_stderr.extend(line),
-            CommandEvent::Terminated(payload) => {
-                convert_success = payload.code == Some(0);
-                break;
-            }
-            _ => {}
+    let state = app.state::<AppState>();
+    let result = {
+        let _job = state.transcription_lock.lock().await;
+        // A decrypted or decoded copy is removed again when this block ends
+        let wav = storage::session_wav(&app, &session_dir)
+            .await
+            .map_err(|e| format!("Cannot re-transcribe: {}", e))?;
+
+        println!(
+            "--- [Debug] Re-transcribing session {} ({:?}) ---",
+            id, options.task
+        );
+        transcribe_session(&app, &session_dir, wav.path(), &options).await
+    };
+
+    storage::spawn_archive(&app, session_dir);
+    result
+}
+
+/// ffmpeg arguments converting (a slice of) `input` to 16 kHz mono WAV.
+fn conversion_args(
+    input: &PreparedInput,
+    wav_path: &Path,
+    slice: &MediaSlice,
+) -> Result<Vec<String>, String> {
+    let mut args = vec!["-y".to_string()];
+    args.extend(input.format_args.iter().cloned());
+
+    // Input-side seeking is fast; output timestamps then start at 0
+    if let Some(start) = slice.start_secs {
+        args.extend(["-ss".to_string(), format!("{:.3}", start)]);
+    }
+    args.extend(["-i".to_string(), input.path.clone()]);
+    if let Some(end) = slice.end_secs {
+        let start = slice.start_secs.unwrap_or(0.0);
+        if end <= start {
+            return Err("End time must be after start time".into());
         }
+        args.extend(["-t".to_string(), format!("{:.3}", end - start)]);
     }
 
-    // Clear child
-    {
-        let mut guard = state.processing_child.lock().unwrap();
-        *guard = None;
+    match slice.audio_stream {
+        Some(index) => args.extend(["-map".to_string(), format!("0:a:{}", index)]),
+        None => args.push("-vn".to_string()),
     }
 
-    if !convert_success {
-        let stderr_str = String::from_utf8_lossy(&convert_stderr);
-        return Err(format!("FFmpeg conversion failed: {}", stderr_str));
+    match slice.channel {
+        AudioChannel::Mix => {}
+        AudioChannel::Left => args.extend(["-af".to_string(), "pan=mono|c0=c0".to_string()]),
+        AudioChannel::Right => args.extend(["-af".to_string(), "pan=mono|c0=c1".to_string()]),
     }
 
-    // 3. Prepare Whisper parameters
+    args.extend([
+        "-ar".to_string(),
+        "16000".to_string(),
+        "-ac".to_string(),
+        "1".to_string(),
+        wav_path.to_string_lossy().to_string(),
+    ]);
+
+    Ok(args)
+}
+
+/// Picks the whisper model for `options`: the name of the model actually
+/// loaded (unknown names fall back as in `get_model_info`) and its path.
+/// Diarization needs tinydiarize's own fine-tuned model, which has to be
+/// downloaded first.
+fn transcription_model(
+    app: &AppHandle,
+    options: &FileTranscribeOptions,
+) -> Result<(String, PathBuf), String> {
+    let model_type = if options.diarize {
+        DIARIZATION_MODEL
+    } else {
+        options.model_type.as_str()
+    };
+    let (model_path, _) = get_model_info(app, model_type)?;
+    if options.diarize && !model_path.exists() {
+        return Err(format!(
+            "Speaker diarization requires the {} model. Please download it first.",
+            DIARIZATION_MODEL
+        ));
+    }
+
+    Ok((resolve_model(model_type).to_string(), model_path))
+}
+
+/// Transcribes a session's 16 kHz WAV and (re)writes its files: transcript.txt,
+/// transcript.json, session.json, plus translation.txt for `WhisperTask::Both`.
+/// Returns the text to deliver, which is the translation when one was requested.
+async fn transcribe_session(
+    app: &AppHandle,
+    session_dir: &Path,
+    wav_path: &Path,
+    options: &FileTranscribeOptions,
+) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let diarize = options.diarize;
+    let (model_type, model_path) = transcription_model(app, options)?;
+
     println!("Running Whisper...");
-    let (model_path, _) = get_model_info(&app, &model_type)?;
+    let resolved = resolve_language(app, &model_path, wav_path, &options.language).await?;
 
     let mut whisper_args = vec![
         "-m".to_string(),
-        model_path.to_str().unwrap().to_string(),
-        "-f".to_string(),
-        wav_path.to_str().unwrap().to_string(),
+        model_path
+            .to_str()
+            .ok_or_else(|| "Invalid model path".to_string())?
+            .to_string(),
         "-t".to_string(),
         "8".to_string(),
         "-l".to_string(),
-        language.clone(),
+        resolved.language.clone(),
         "--prompt".to_string(),
-        prompt.clone(),
+        prompt_with_vocabulary(app, &options.prompt),
+        // -nt means No Timestamps (plain text to stdout)
+        "-nt".to_string(),
     ];
 
-    // Toggle between timestamps (SRT) and plain text
-    if with_timestamps {
-        // -osrt outputs a .srt file.
-        // whisper.cpp convention: input.wav -> input.wav.srt
-        whisper_args.push("-osrt".to_string());
-    } else {
-        // -nt means No Timestamps (plain text to stdout)
-        whisper_args.push("-nt".to_string());
+    let settings = state.settings.lock().unwrap().clone();
+    if diarize {
+        // Marks speaker turns in the JSON output (`speaker_turn_next`)
+        whisper_args.push("-tdrz".to_string());
+    } else if settings.dtw_word_timestamps {
+        // DTW presets are named after the model actually loaded, e.g.
+        // large-v3-turbo -> large.v3.turbo
+        whisper_args.push("-dtw".to_string());
+        whisper_args.push(model_type.replace('-', "."));
     }
 
-    // 4. Run Whisper
-    let mut whisper_rx = {
-        let (rx, child) = app
-            .shell()
-            .sidecar("whisper-cli")
-            .map_err(|e| e.to_string())?
-            .args(whisper_args)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-
-        let mut guard = state.processing_child.lock().unwrap();
-        *guard = Some(child);
-        rx
+    // Long files go through whisper in chunks cut at silences
+    let duration_ms = wav_duration_ms(wav_path)?;
+    let chunk_ms = settings.chunk_secs as i64 * 1000;
+    let chunks = if chunk_ms > 0 && duration_ms > chunk_ms {
+        let silences = detect_silences(app, wav_path).await?;
+        plan_chunks(duration_ms, &silences, chunk_ms)
+    } else {
+        vec![Chunk::whole(duration_ms)]
     };
 
-    let mut whisper_stdout = Vec::new();
-    let mut whisper_stderr = Vec::new();
-    let mut whisper_success = false;
-    while let Some(event) = whisper_rx.recv().await {
-        match event {
-            CommandEvent::Stdout(line) => whisper_stdout.extend(line),
-            CommandEvent::Stderr(line) => whisper_stderr.extend(line),
-            CommandEvent::Terminated(payload) => {
-                whisper_success = payload.code == Some(0);
-                break;
-            }
-            _ => {}
+    // Re-transcriptions keep speaker renames, in transcript.txt too
+    let mut meta = load_meta(session_dir);
+    let result = transcribe_chunks(
+        app,
+        session_dir,
+        wav_path,
+        &whisper_args,
+        &chunks,
+        options,
+        diarize.then_some(settings.diarization_speakers),
+        &meta.speakers,
+    )
+    .await;
+
+    // Chunk WAVs are only needed while whisper runs
+    if chunks.len() > 1 {
+        for i in 0..chunks.len() {
+            let _ = tokio::fs::remove_file(chunk_wav_path(session_dir, i)).await;
         }
     }
+    let (mut outputs, whisper_stderr) = result?;
 
-    // Clear child
-    {
-        let mut guard = state.processing_child.lock().unwrap();
-        *guard = None;
+    // Exports and word timings follow the first run (the only one for `Translate`)
+    let segments = &outputs[0].1;
+    if !segments.is_empty() {
+        save_segments(session_dir, segments)?;
+    } else {
+        let _ = tokio::fs::remove_file(session_dir.join(SEGMENTS_FILE)).await;
     }
 
-    if !whisper_success {
-        return Err("Whisper transcription failed or was aborted".into());
+    meta.task = options.task;
+    meta.diarized = diarize;
+    meta.with_timestamps = options.with_timestamps;
+    meta.max_chars = options.max_chars;
+    meta.slice = options.slice.clone();
+    // transcript.txt is whisper output again; earlier edits stay in revisions.json
+    meta.edited_at = None;
+    resolved.record_in(&mut meta);
+    save_meta(session_dir, &meta)?;
+
+    // Persist transcript.txt (and translation.txt when both were requested)
+    let transcript_body = if !outputs[0].0.is_empty() {
+        outputs[0].0.clone()
+    } else {
+        format!("(empty)\n\nstderr:\n{}", whisper_stderr)
+    };
+
+    crypto::write(&session_dir.join("transcript.txt"), transcript_body)
+        .map_err(|e| format!("Failed to write transcript: {}", e))?;
+
+    let translation_path = session_dir.join(TRANSLATION_FILE);
+    match outputs.get(1) {
+        Some((translation, _)) => crypto::write(&translation_path, translation)
+            .map_err(|e| format!("Failed to write translation: {}", e))?,
+        None => {
+            let _ = tokio::fs::remove_file(&translation_path).await;
+        }
     }
 
-    let final_text: String;
+    Ok(outputs.pop().map(|(text, _)| text).unwrap_or_default())
+}
 
-    if with_timestamps {
-        // Attempt to read the generated .srt file
-        // Since input was `.../input_16k.wav`, output should be `.../input_16k.wav.srt`
-        let srt_path = wav_path.with_extension("wav.srt");
+fn chunk_wav_path(session_dir: &Path, index: usize) -> PathBuf {
+    session_dir.join(format!("chunk_{:03}.wav", index))
+}
 
-        if srt_path.exists() {
-            println!("Reading generated SRT file: {:?}", srt_path);
-            final_text = tokio::fs::read_to_string(&srt_path)
-                .await
-                .map_err(|e| format!("Failed to read SRT file: {}", e))?;
-        } else {
-            // Fallback to stdout if SRT file generation failed
-            println!("Warning: SRT file not found, falling back to stdout");
-            final_text = String::from_utf8_lossy(&whisper_stdout).trim().to_string();
+/// Runs every requested pass over every chunk, reporting `transcription-progress`.
+/// Returns one rendered text + segments per pass, and the last whisper stderr.
+#[allow(clippy::too_many_arguments)]
+async fn transcribe_chunks(
+    app: &AppHandle,
+    session_dir: &Path,
+    wav_path: &Path,
+    whisper_args: &[String],
+    chunks: &[Chunk],
+    options: &FileTranscribeOptions,
+    speaker_count: Option<usize>,
+    renames: &BTreeMap<String, String>,
+) -> Result<(Vec<(String, Vec<Segment>)>, String), String> {
+    let chunked = chunks.len() > 1;
+
+    // Cut the chunk WAVs once; every pass reuses them
+    if chunked {
+        println!("Splitting into {} chunks...", chunks.len());
+        for (i, chunk) in chunks.iter().enumerate() {
+            let convert = run_sidecar(
+                app,
+                "ffmpeg",
+                [
+                    "-y".to_string(),
+                    "-ss".to_string(),
+                    format!("{:.3}", chunk.start_ms as f64 / 1000.0),
+                    "-t".to_string(),
+                    format!("{:.3}", chunk.len_ms() as f64 / 1000.0),
+                    "-i".to_string(),
+                    wav_path.to_string_lossy().to_string(),
+                    "-c".to_string(),
+                    "copy".to_string(),
+                    chunk_wav_path(session_dir, i).to_string_lossy().to_string(),
+                ],
+            )
+            .await?;
+
+            if !convert.success {
+                let stderr_str = String::from_utf8_lossy(&convert.stderr);
+                return Err(format!("FFmpeg chunking failed: {}", stderr_str));
+            }
         }
-    } else {
-        // Plain text mode: read directly from stdout
-        final_text = String::from_utf8_lossy(&whisper_stdout).trim().to_string();
     }
 
-    let whisper_stderr = String::from_utf8_lossy(&whisper_stderr).to_string();
-
-    // 5. Persist and return
-    let transcript_body = if !final_text.is_empty() {
-        final_text.clone()
-    } else {
-        format!("(empty)\n\nstderr:\n{}", whisper_stderr)
-    };
+    let passes = options.task.passes();
+    let audio_ms: i64 = chunks.iter().map(Chunk::len_ms).sum();
+    let mut progress = ProgressTracker::new(
+        app,
+        audio_ms * passes.len() as i64,
+        chunks.len() * passes.len(),
+    );
+    // One pass per requested output: original first, then the translation
+    let mut outputs = Vec::new();
+    let mut whisper_stderr = String::new();
+    for (pass, &translate) in passes.iter().enumerate() {
+        let mut args = whisper_args.to_vec();
+        if translate {
+            args.push("--translate".to_string());
+        }
+        let json_base = session_dir.join(if translate { "whisper_en" } else { "whisper" });
+
+        let mut stdout = String::new();
+        let mut results = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let chunk_wav = if chunked {
+                chunk_wav_path(session_dir, i)
+            } else {
+                wav_path.to_path_buf()
+            };
+            let run_index = pass * chunks.len() + i + 1;
+            let run_ms = chunk.len_ms();
+
+            let mut run = run_whisper(app, &args, &chunk_wav, &json_base, speaker_count, |p| {
+                progress.update(run_index, run_ms, p)
+            })
+            .await?;
+            progress.finish_run(run_ms);
+
+            // tinydiarize labels restart in every chunk; renaming fixes them up
+            shift_segments(&mut run.segments, chunk.start_ms);
+            results.push(run.segments);
+            stdout = run.stdout;
+            whisper_stderr = run.stderr;
+        }
 
-    tokio::fs::write(&transcript_path, transcript_body)
-        .await
-        .map_err(|e| format!("Failed to write transcript: {}", e))?;
+        let mut segments = if chunked {
+            stitch(chunks, results)
+        } else {
+            results.pop().unwrap_or_default()
+        };
+        // Refer to the original file when only a slice of it was transcribed
+        shift_segments(&mut segments, options.slice.offset_ms());
+
+        let text = if segments.is_empty() {
+            stdout
+        } else if options.with_timestamps {
+            match options.max_chars {
+                Some(max_chars) => to_srt(&split_segments(&segments, max_chars), renames),
+                None => to_srt(&segments, renames),
+            }
+        } else if speaker_count.is_some() || chunked {
+            // Stitched chunks have no single stdout to use
+            to_text(&segments, renames)
+        } else {
+            // Plain text mode: read directly from stdout
+            stdout
+        };
 
-    // Auto-copy
-    if !final_text.is_empty() {
-        let _ = app.clipboard().write_text(final_text.clone());
+        outputs.push((text, segments));
     }
 
-    Ok(final_text)
+    Ok((outputs, whisper_stderr))
 }
 
 #[tauri::command]
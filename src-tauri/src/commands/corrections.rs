@@ -0,0 +1,34 @@
+use crate::corrections;
+use crate::types::{CorrectionSuggestion, CorrectionTarget, TermCorrection};
+use tauri::AppHandle;
+
+/// Corrections made in at least `min_count` (default 2) edited transcripts.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_correction_suggestions(
+    app: AppHandle,
+    min_count: Option<usize>,
+) -> Result<Vec<CorrectionSuggestion>, String> {
+    // Diffs every edited transcript, which is too slow for the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        corrections::suggestions(&app, min_count.unwrap_or(2))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Adds a correction as a replacement rule (to `profile`, by default the
+/// active one) or to the prompt vocabulary.
+#[tauri::command]
+pub fn accept_correction(
+    app: AppHandle,
+    correction: TermCorrection,
+    target: CorrectionTarget,
+    profile: Option<String>,
+) -> Result<(), String> {
+    corrections::accept(&app, correction, target, profile)
+}
+
+#[tauri::command]
+pub fn dismiss_correction(app: AppHandle, correction: TermCorrection) -> Result<(), String> {
+    corrections::dismiss(&app, correction)
+}
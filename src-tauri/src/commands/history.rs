@@ -1,14 +1,31 @@
-use crate::types::HistoryItem;
-use crate::utils::get_recordings_dir;
+use crate::backup;
+use crate::crypto;
+use crate::session::{
+    load_meta, load_revisions, load_segments, save_meta, save_revisions, RevisionSource,
+    TranscriptRevision, TRANSLATION_FILE,
+};
+use crate::transcript::{split_segments, to_srt, to_text, to_vtt, Word};
+use crate::trash;
+use crate::types::{
+    HistoryDetails, HistoryFilter, HistoryItem, HistoryRange, ImportReport, TrashItem, TrashReport,
+};
+use crate::utils::{existing_session_dir, get_recordings_dir, parse_session_id, SESSION_ID_FORMAT};
+use chrono::Local;
 use std::fs;
+use std::path::Path;
 use tauri::AppHandle;
 
+/// Finished sessions, pinned first and then newest first, narrowed by `filter`.
 #[tauri::command]
-pub async fn get_history(app: AppHandle) -> Result<Vec<HistoryItem>, String> {
+pub async fn get_history(
+    app: AppHandle,
+    filter: Option<HistoryFilter>,
+) -> Result<Vec<HistoryItem>, String> {
     let recordings_dir = get_recordings_dir(&app)?;
     if !recordings_dir.exists() {
         return Ok(vec![]);
     }
+    let filter = filter.unwrap_or_default();
 
     let mut history = Vec::new();
 
@@ -26,32 +43,294 @@ pub async fn get_history(app: AppHandle) -> Result<Vec<HistoryItem>, String> {
 
             let transcript_path = path.join("transcript.txt");
             if transcript_path.exists() {
-                let text = fs::read_to_string(&transcript_path).unwrap_or_default();
+                let text = crypto::read_to_string(&transcript_path).unwrap_or_default();
+                let translation = crypto::read_to_string(&path.join(TRANSLATION_FILE)).ok();
+                let meta = load_meta(&path);
 
-                // session_id is "YYYY-MM-DD_HH-MM-SS"
-                history.push(HistoryItem {
-                    id: session_id.clone(),
+                // "YYYY-MM-DD_HH-MM-SS", without the same-second counter
+                let timestamp = match parse_session_id(&session_id) {
+                    Some((started, _)) => started.format(SESSION_ID_FORMAT).to_string(),
+                    None => session_id.clone(),
+                };
+                let item = HistoryItem {
+                    id: session_id,
                     text,
-                    timestamp: session_id,
-                });
+                    timestamp,
+                    translation,
+                    title: meta.title,
+                    tags: meta.tags,
+                    pinned: meta.pinned,
+                    favorite: meta.favorite,
+                    edited: meta.edited_at.is_some(),
+                };
+                if matches_filter(&item, &filter) {
+                    history.push(item);
+                }
             }
         }
     }
 
-    // Sort by session_id descending (newest first)
-    history.sort_by(|a, b| b.id.cmp(&a.id));
+    // Pinned, then newest first; `-10` sorts after `-9`, unrecognized folder names go last
+    history.sort_by(|a, b| {
+        (b.pinned, parse_session_id(&b.id), &b.id).cmp(&(a.pinned, parse_session_id(&a.id), &a.id))
+    });
 
     Ok(history)
 }
 
+fn matches_filter(item: &HistoryItem, filter: &HistoryFilter) -> bool {
+    let query = filter.query.as_deref().map(str::trim).unwrap_or("");
+    let matches_query = query.is_empty() || {
+        let query = query.to_lowercase();
+        item.text.to_lowercase().contains(&query)
+            || item
+                .title
+                .as_ref()
+                .is_some_and(|t| t.to_lowercase().contains(&query))
+    };
+
+    // Timestamps start with the date, so plain string comparison works
+    let date = item.timestamp.get(..10).unwrap_or(&item.timestamp);
+    matches_query
+        && filter.from.as_deref().is_none_or(|from| date >= from)
+        && filter.to.as_deref().is_none_or(|to| date <= to)
+        && filter.tags.iter().all(|tag| item.tags.contains(tag))
+        && filter.pinned.is_none_or(|p| item.pinned == p)
+        && filter.favorite.is_none_or(|f| item.favorite == f)
+}
+
+/// Replaces a session's transcript with the user's correction. The whisper
+/// output and every edit are kept in revisions.json.
 #[tauri::command]
-pub async fn delete_history_item(app: AppHandle, id: String) -> Result<(), String> {
-    let recordings_dir = get_recordings_dir(&app)?;
-    let item_dir = recordings_dir.join(id);
+pub async fn update_history_item(app: AppHandle, id: String, text: String) -> Result<(), String> {
+    let session_dir = existing_session_dir(&app, &id)?;
+    let transcript_path = session_dir.join("transcript.txt");
+    let current = crypto::read_to_string(&transcript_path)?;
+    if current == text {
+        return Ok(());
+    }
 
-    if item_dir.exists() && item_dir.is_dir() {
-        fs::remove_dir_all(item_dir).map_err(|e| e.to_string())?;
+    let mut meta = load_meta(&session_dir);
+    let mut revisions = load_revisions(&session_dir);
+    let now = Local::now().to_rfc3339();
+
+    // First edit, or the session was re-transcribed since the last one
+    if meta.edited_at.is_none() || revisions.last().is_none_or(|r| r.text != current) {
+        revisions.push(TranscriptRevision {
+            source: RevisionSource::Whisper,
+            text: current,
+            created_at: now.clone(),
+        });
     }
+    revisions.push(TranscriptRevision {
+        source: RevisionSource::User,
+        text: text.clone(),
+        created_at: now.clone(),
+    });
+    save_revisions(&session_dir, &revisions)?;
+
+    crypto::write(&transcript_path, text)?;
+    meta.edited_at = Some(now);
+    save_meta(&session_dir, &meta)
+}
+
+/// Whisper output and user edits of a session's transcript, oldest first.
+#[tauri::command]
+pub async fn get_history_revisions(
+    app: AppHandle,
+    id: String,
+) -> Result<Vec<TranscriptRevision>, String> {
+    let session_dir = existing_session_dir(&app, &id)?;
+    Ok(load_revisions(&session_dir))
+}
+
+/// Sets a session's title, tags, pinned or favorite flag.
+#[tauri::command]
+pub async fn update_history_details(
+    app: AppHandle,
+    id: String,
+    details: HistoryDetails,
+) -> Result<(), String> {
+    let session_dir = existing_session_dir(&app, &id)?;
+    let mut meta = load_meta(&session_dir);
+
+    if let Some(title) = details.title {
+        let title = title.trim();
+        meta.title = (!title.is_empty()).then(|| title.to_string());
+    }
+    if let Some(tags) = details.tags {
+        meta.tags.clear();
+        for tag in tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !meta.tags.iter().any(|t| t == tag) {
+                meta.tags.push(tag.to_string());
+            }
+        }
+    }
+    if let Some(pinned) = details.pinned {
+        meta.pinned = pinned;
+    }
+    if let Some(favorite) = details.favorite {
+        meta.favorite = favorite;
+    }
+
+    save_meta(&session_dir, &meta)
+}
+
+/// Moves a session to the trash. Returns its trash id, for undo.
+#[tauri::command]
+pub async fn delete_history_item(app: AppHandle, id: String) -> Result<String, String> {
+    let mut report = trash::move_to_trash(&app, &[id]).await?;
+    match report.failed.pop_first() {
+        Some((_, e)) => Err(e),
+        None => Ok(report.trashed.remove(0)),
+    }
+}
+
+/// Moves every session matching `filter` to the trash. Pinned sessions are
+/// only included when the filter asks for them. Sessions that can't be moved
+/// are reported without stopping the rest.
+#[tauri::command]
+pub async fn delete_history_items(
+    app: AppHandle,
+    filter: HistoryFilter,
+) -> Result<TrashReport, String> {
+    if filter.is_empty() {
+        return Err("Refusing to delete without a filter".into());
+    }
+    let include_pinned = filter.pinned == Some(true);
+
+    let ids: Vec<String> = get_history(app.clone(), Some(filter))
+        .await?
+        .into_iter()
+        .filter(|item| include_pinned || !item.pinned)
+        .map(|item| item.id)
+        .collect();
+    trash::move_to_trash(&app, &ids).await
+}
+
+#[tauri::command]
+pub fn get_trash(app: AppHandle) -> Result<Vec<TrashItem>, String> {
+    trash::list(&app)
+}
+
+/// Puts a trashed session back. Returns its id in the history.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn restore_history_item(app: AppHandle, trash_id: String) -> Result<String, String> {
+    trash::restore(&app, &trash_id).await
+}
+
+/// Deletes trashed sessions for good (all of them without `trash_ids`).
+#[tauri::command(rename_all = "camelCase")]
+pub fn purge_trash(app: AppHandle, trash_ids: Option<Vec<String>>) -> Result<usize, String> {
+    trash::purge(&app, trash_ids.as_deref())
+}
+
+/// Renames a diarized speaker (e.g. `Speaker 1` -> `Alice`) for one session.
+/// An empty name restores the original label.
+#[tauri::command]
+pub async fn rename_speaker(
+    app: AppHandle,
+    id: String,
+    speaker: String,
+    name: String,
+) -> Result<(), String> {
+    let session_dir = existing_session_dir(&app, &id)?;
+    let segments = load_segments(&session_dir).ok_or("Session has no timed transcript")?;
+
+    let mut meta = load_meta(&session_dir);
+    if !meta.diarized {
+        return Err("Session was not diarized".into());
+    }
+
+    let name = name.trim();
+    if name.is_empty() {
+        meta.speakers.remove(&speaker);
+    } else {
+        meta.speakers.insert(speaker, name.to_string());
+    }
+    save_meta(&session_dir, &meta)?;
+
+    // A user edit wins; the new names still apply to timed exports
+    if meta.edited_at.is_some() {
+        return Ok(());
+    }
+
+    // Keep transcript.txt (what history shows) in sync with the new names
+    let text = if meta.with_timestamps {
+        match meta.max_chars {
+            Some(max_chars) => to_srt(&split_segments(&segments, max_chars), &meta.speakers),
+            None => to_srt(&segments, &meta.speakers),
+        }
+    } else {
+        to_text(&segments, &meta.speakers)
+    };
+    crypto::write(&session_dir.join("transcript.txt"), text)?;
 
     Ok(())
 }
+
+/// Renders a session as `txt`, `srt` or `vtt` (with speaker names when diarized).
+/// `max_chars` splits subtitle cues at word boundaries.
+/// Writes to `path` when given, and returns the rendered content either way.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_transcript(
+    app: AppHandle,
+    id: String,
+    format: String,
+    path: Option<String>,
+    max_chars: Option<usize>,
+) -> Result<String, String> {
+    let session_dir = existing_session_dir(&app, &id)?;
+    let meta = load_meta(&session_dir);
+    let segments = match (load_segments(&session_dir), max_chars) {
+        (Some(segments), Some(max_chars)) if format != "txt" => {
+            Some(split_segments(&segments, max_chars))
+        }
+        (segments, _) => segments,
+    };
+
+    // Segments hold whisper's text; a corrected transcript replaces it for plain text
+    let segments = segments.filter(|_| format != "txt" || meta.edited_at.is_none());
+    let content = match (format.as_str(), segments) {
+        ("srt", Some(segments)) => to_srt(&segments, &meta.speakers),
+        ("vtt", Some(segments)) => to_vtt(&segments, &meta.speakers),
+        ("txt", Some(segments)) => to_text(&segments, &meta.speakers),
+        // Dictations, older and edited sessions
+        ("txt", None) => crypto::read_to_string(&session_dir.join("transcript.txt"))?,
+        ("srt" | "vtt", None) => return Err("Session has no timed transcript".into()),
+        _ => return Err(format!("Unsupported export format: {}", format)),
+    };
+
+    if let Some(path) = path {
+        fs::write(&path, &content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+
+    Ok(content)
+}
+
+/// Word-level timings of a session, in order, for seeking to an exact word.
+#[tauri::command]
+pub async fn get_word_timings(app: AppHandle, id: String) -> Result<Vec<Word>, String> {
+    let session_dir = existing_session_dir(&app, &id)?;
+    let segments = load_segments(&session_dir).ok_or("Session has no timed transcript")?;
+
+    Ok(segments.into_iter().flat_map(|s| s.words).collect())
+}
+
+/// Writes the sessions in `range` (all of them by default) with their audio
+/// and metadata to a tar archive at `path`. Returns the number exported.
+#[tauri::command]
+pub async fn export_history(
+    app: AppHandle,
+    range: Option<HistoryRange>,
+    path: String,
+) -> Result<usize, String> {
+    backup::export(&app, &range.unwrap_or_default(), Path::new(&path)).await
+}
+
+/// Merges an `export_history` archive into the history.
+#[tauri::command]
+pub async fn import_history(app: AppHandle, path: String) -> Result<ImportReport, String> {
+    backup::import(&app, Path::new(&path)).await
+}
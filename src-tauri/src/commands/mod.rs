@@ -1,5 +1,9 @@
 pub mod audio;
+pub mod corrections;
 pub mod history;
 pub mod model;
+pub mod profile;
+pub mod settings;
+pub mod storage;
 pub mod system;
 pub mod window;
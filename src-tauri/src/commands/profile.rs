@@ -0,0 +1,63 @@
+use crate::frontmost::{frontmost_app, FrontmostApp};
+use crate::profiles::switch_profile;
+use crate::settings::modify_settings;
+use crate::state::AppState;
+use crate::types::{AppProfileRule, DictationProfile};
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub fn get_profiles(state: State<'_, AppState>) -> Vec<DictationProfile> {
+    state.settings.lock().unwrap().profiles.clone()
+}
+
+#[tauri::command]
+pub fn get_active_profile(state: State<'_, AppState>) -> Option<String> {
+    state.settings.lock().unwrap().active_profile.clone()
+}
+
+/// Creates the profile, or replaces the existing one with the same name.
+#[tauri::command]
+pub fn save_profile(app: AppHandle, profile: DictationProfile) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("Profile name cannot be empty".into());
+    }
+
+    modify_settings(&app, |s| {
+        match s.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => s.profiles.push(profile),
+        }
+    })
+}
+
+#[tauri::command]
+pub fn delete_profile(app: AppHandle, name: String) -> Result<(), String> {
+    modify_settings(&app, |s| {
+        s.profiles.retain(|p| p.name != name);
+        if s.active_profile.as_deref() == Some(name.as_str()) {
+            s.active_profile = None;
+        }
+    })
+}
+
+/// `None` clears the active profile.
+#[tauri::command]
+pub fn set_active_profile(app: AppHandle, name: Option<String>) -> Result<(), String> {
+    switch_profile(&app, name)
+}
+
+#[tauri::command]
+pub fn get_app_profile_rules(state: State<'_, AppState>) -> Vec<AppProfileRule> {
+    state.settings.lock().unwrap().app_profile_rules.clone()
+}
+
+#[tauri::command]
+pub fn set_app_profile_rules(app: AppHandle, rules: Vec<AppProfileRule>) -> Result<(), String> {
+    modify_settings(&app, |s| s.app_profile_rules = rules)
+}
+
+/// What the detector currently sees, to help users write rules.
+#[tauri::command]
+pub fn get_frontmost_app() -> Option<FrontmostApp> {
+    frontmost_app()
+}
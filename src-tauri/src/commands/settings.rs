@@ -0,0 +1,49 @@
+use crate::api;
+#[cfg(unix)]
+use crate::control;
+use crate::crypto;
+use crate::hooks::{run_with_retries, HookPayload};
+use crate::settings::save_settings;
+use crate::state::AppState;
+use crate::types::{AppSettings, TranscriptionHook};
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub fn get_settings(state: State<'_, AppState>) -> AppSettings {
+    state.settings.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn update_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    settings: AppSettings,
+) -> Result<(), String> {
+    // Before saving: turning encryption on fails if no key can be stored
+    if state.settings.lock().unwrap().encrypt_sessions != settings.encrypt_sessions {
+        crypto::init(&app, settings.encrypt_sessions)?;
+    }
+    save_settings(&app, &settings)?;
+    let previous = std::mem::replace(&mut *state.settings.lock().unwrap(), settings.clone());
+
+    let api_changed = previous.api_enabled != settings.api_enabled
+        || previous.api_bind_address != settings.api_bind_address
+        || previous.api_token != settings.api_token;
+    if api_changed {
+        api::restart(&app)?;
+    }
+
+    #[cfg(unix)]
+    if previous.control_socket_enabled != settings.control_socket_enabled {
+        control::restart(&app)?;
+    }
+
+    Ok(())
+}
+
+/// Fires `hook` once with a sample transcript, without retries or logging.
+#[tauri::command]
+pub async fn test_hook(hook: TranscriptionHook) -> Result<String, String> {
+    let hook = TranscriptionHook { retries: 0, ..hook };
+    run_with_retries(&hook, &HookPayload::sample(), None).await
+}
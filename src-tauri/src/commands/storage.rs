@@ -0,0 +1,55 @@
+use crate::crypto;
+use crate::state::AppState;
+use crate::storage;
+use crate::types::{ArchiveReport, AudioArchive, StorageUsage};
+use crate::utils::existing_session_dir;
+use tauri::ipc::Response;
+use tauri::{AppHandle, Manager};
+
+/// Sizes of the recordings folder by category (raw, audio, text, other).
+#[tauri::command]
+pub async fn storage_usage(app: AppHandle) -> Result<StorageUsage, String> {
+    storage::usage(&app)
+}
+
+/// Applies the storage policy now instead of waiting for the background task.
+/// Returns the bytes freed.
+#[tauri::command]
+pub async fn run_storage_cleanup(app: AppHandle) -> Result<u64, String> {
+    storage::enforce(&app).await
+}
+
+/// Compresses the audio of all existing sessions, by default to the configured
+/// `archive_format`.
+#[tauri::command]
+pub async fn compress_recordings(
+    app: AppHandle,
+    format: Option<AudioArchive>,
+) -> Result<ArchiveReport, String> {
+    let format = format.unwrap_or_else(|| {
+        app.state::<AppState>()
+            .settings
+            .lock()
+            .unwrap()
+            .storage
+            .archive_format
+    });
+    storage::compress_all(&app, format).await
+}
+
+/// The session's audio as WAV bytes, decoded and decrypted as needed. Bytes
+/// rather than a path, so no plain copy has to outlive the call.
+#[tauri::command]
+pub async fn get_session_audio(app: AppHandle, id: String) -> Result<Response, String> {
+    let session_dir = existing_session_dir(&app, &id)?;
+    let wav = storage::session_wav(&app, &session_dir).await?;
+    let data = std::fs::read(wav.path()).map_err(|e| e.to_string())?;
+    Ok(Response::new(data))
+}
+
+/// Encrypts every existing session, or decrypts them when `encrypt_sessions`
+/// is off. Returns the number of files converted.
+#[tauri::command]
+pub async fn migrate_encryption(app: AppHandle) -> Result<usize, String> {
+    crypto::migrate(&app).await
+}
@@ -1,7 +1,11 @@
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager, State};
 use std::process::Command;
 use macos_accessibility_client::accessibility;
 use cpal::traits::{HostTrait, DeviceTrait, StreamTrait};
+use crate::settings::modify_settings;
+use crate::shortcut::register_shortcut_bindings;
+use crate::state::AppState;
+use crate::types::{ShortcutAction, ShortcutBinding, ShortcutBindingStatus};
 
 #[tauri::command]
 pub fn check_accessibility_permission() -> bool {
@@ -86,21 +90,83 @@ pub async fn request_microphone_permission(_app: AppHandle) -> bool {
     }
 }
 
+/// Updates the primary recording shortcut (the first toggle / push-to-talk binding).
+/// `mode` is optional so existing callers keep the previously selected action.
 #[tauri::command]
-pub fn update_global_shortcut(app: AppHandle, shortcut_str: String) -> Result<(), String> {
-    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
-    use std::str::FromStr;
+pub fn update_global_shortcut(
+    app: AppHandle,
+    shortcut_str: String,
+    mode: Option<ShortcutAction>,
+) -> Result<(), String> {
+    println!("Updating shortcut to: {} (mode: {:?})", shortcut_str, mode);
+
+    if let Some(action) = mode {
+        if !matches!(action, ShortcutAction::Toggle | ShortcutAction::PushToTalk) {
+            return Err(format!("{:?} is not a recording mode", action));
+        }
+    }
+
+    let mut bindings = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .shortcuts
+        .clone();
+    let primary = bindings.iter().position(|b| {
+        matches!(
+            b.action,
+            ShortcutAction::Toggle | ShortcutAction::PushToTalk
+        )
+    });
+
+    match primary {
+        Some(i) => {
+            bindings[i].shortcut = shortcut_str.clone();
+            if let Some(action) = mode {
+                bindings[i].action = action;
+            }
+        }
+        None => bindings.insert(
+            0,
+            ShortcutBinding {
+                shortcut: shortcut_str.clone(),
+                action: mode.unwrap_or(ShortcutAction::Toggle),
+                profile_name: None,
+                profile: None,
+            },
+        ),
+    }
 
-    println!("Updating shortcut to: {}", shortcut_str);
+    let statuses = apply_shortcut_bindings(&app, bindings)?;
+    match statuses.into_iter().find(|s| s.shortcut == shortcut_str) {
+        Some(status) if !status.registered => Err(status.error.unwrap_or_default()),
+        _ => Ok(()),
+    }
+}
 
-    let _ = app.global_shortcut().unregister_all();
+#[tauri::command]
+pub fn get_shortcut_bindings(state: State<'_, AppState>) -> Vec<ShortcutBinding> {
+    state.settings.lock().unwrap().shortcuts.clone()
+}
 
-    let shortcut = Shortcut::from_str(&shortcut_str).map_err(|e| e.to_string())?;
-    app.global_shortcut()
-        .register(shortcut)
-        .map_err(|e| e.to_string())?;
+/// Persists and registers the full shortcut map. Bindings that fail to register
+/// are still saved (so the user can fix them) and reported in the returned statuses.
+#[tauri::command]
+pub fn set_shortcut_bindings(
+    app: AppHandle,
+    bindings: Vec<ShortcutBinding>,
+) -> Result<Vec<ShortcutBindingStatus>, String> {
+    apply_shortcut_bindings(&app, bindings)
+}
 
-    Ok(())
+fn apply_shortcut_bindings(
+    app: &AppHandle,
+    bindings: Vec<ShortcutBinding>,
+) -> Result<Vec<ShortcutBindingStatus>, String> {
+    let statuses = register_shortcut_bindings(app, &bindings);
+    modify_settings(app, |s| s.shortcuts = bindings)?;
+    Ok(statuses)
 }
 
 #[tauri::command]
@@ -15,4 +15,11 @@ pub const MODELS: &[(&str, &str)] = &[
         "large-v3-turbo",
         "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo.bin",
     ),
+    (
+        "small.en-tdrz",
+        "https://huggingface.co/akashmjn/tinydiarize-whisper.cpp/resolve/main/ggml-small.en-tdrz.bin",
+    ),
 ];
+
+/// tinydiarize fine-tune used for speaker turn detection (`-tdrz`). English only.
+pub const DIARIZATION_MODEL: &str = "small.en-tdrz";
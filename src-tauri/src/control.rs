@@ -0,0 +1,348 @@
+//! Unix control socket for window-manager keybindings and scripts, where
+//! global shortcuts are unreliable (e.g. Wayland).
+//!
+//! Each line a client writes is one JSON command and gets one JSON reply:
+//!
+//!     {"cmd": "start", "profile": "notes"}  ->  {"ok": true, "data": "<session id>"}
+//!     {"cmd": "stop"}                       ->  {"ok": true, "data": "<transcript>"}
+//!
+//! Commands: `start`, `stop`, `toggle`, `cancel`, `status`,
+//! `get-last-transcript`, `subscribe`. After `subscribe` the connection only
+//! receives `{"event": ..., "payload": ...}` lines until the client hangs up.
+//! The companion client is `whisper-flow ctl <command>`.
+
+use crate::shortcut::{handle_cancel, start_with_profile, stop_and_deliver};
+use crate::state::AppState;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use tauri::{AppHandle, Listener, Manager};
+
+pub const CTL_USAGE: &str = "Usage: whisper-flow ctl <COMMAND> [--profile <NAME>]
+
+Commands:
+  start                 Start recording (optionally with a profile)
+  stop                  Stop recording and print the transcript
+  toggle                Start or stop, whichever applies
+  cancel                Discard the recording or abort the transcription
+  status                Print the current state as JSON
+  get-last-transcript   Print the last transcript
+  subscribe             Print events as JSON lines until interrupted";
+
+/// Events forwarded to `subscribe` connections.
+const EVENTS: &[&str] = &[
+    "recording-state",
+    "recording-ready",
+    "transcription-result",
+    "transcription-error",
+    "transcription-progress",
+    "profile-changed",
+];
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+enum Command {
+    Start {
+        #[serde(default)]
+        profile: Option<String>,
+    },
+    Stop,
+    Toggle {
+        #[serde(default)]
+        profile: Option<String>,
+    },
+    Cancel,
+    Status,
+    GetLastTranscript,
+    Subscribe,
+}
+
+/// `$XDG_RUNTIME_DIR/whisper-flow/control.sock`, or the same in a per-user
+/// folder in the temp dir.
+pub fn socket_path() -> PathBuf {
+    socket_dir().join("control.sock")
+}
+
+fn socket_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("whisper-flow"),
+        None => std::env::temp_dir().join(format!(
+            "whisper-flow-{}",
+            std::env::var("USER").unwrap_or_default()
+        )),
+    }
+}
+
+/// Creates the socket's folder, or checks an existing one, as private to us.
+/// Anyone who can connect can dictate into the focused app, and the socket must
+/// never be reachable, not even between `bind` and a `chmod` on it.
+fn ensure_private_dir(dir: &Path) -> Result<(), String> {
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("Failed to create {}: {}", dir.display(), e)),
+    }
+
+    let meta = std::fs::symlink_metadata(dir).map_err(|e| e.to_string())?;
+    if !meta.is_dir() {
+        return Err(format!("{} is not a folder", dir.display()));
+    }
+    // Only the owner may chmod, so this also fails for a folder someone else planted
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+        .map_err(|e| format!("{} is not ours: {}", dir.display(), e))
+}
+
+/// Stops the running socket and starts a new one if it is enabled. Called at
+/// startup and whenever `control_socket_enabled` changes. Connections already
+/// open are served until the client hangs up.
+pub fn restart(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if let Some(stop) = state.control_socket.lock().unwrap().take() {
+        stop.store(true, Ordering::SeqCst);
+        // Wakes the accept loop so it sees the flag
+        let _ = UnixStream::connect(socket_path());
+        let _ = std::fs::remove_file(socket_path());
+    }
+
+    if !state.settings.lock().unwrap().control_socket_enabled {
+        return Ok(());
+    }
+    let stop = start(app)?;
+    *state.control_socket.lock().unwrap() = Some(stop);
+    Ok(())
+}
+
+/// Binds the socket and serves it in the background until the returned flag
+/// is set. A socket left behind by a crashed instance is replaced; one that
+/// still answers belongs to a running instance and is left alone.
+fn start(app: &AppHandle) -> Result<Arc<AtomicBool>, String> {
+    ensure_private_dir(&socket_dir())?;
+    let path = socket_path();
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(format!(
+                "Control socket {} is in use by another instance",
+                path.display()
+            ));
+        }
+        std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+
+    let listener = UnixListener::bind(&path)
+        .map_err(|e| format!("Failed to bind control socket {}: {}", path.display(), e))?;
+    // The folder already keeps others out; this is belt and braces
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| e.to_string())?;
+    println!("[Rust] Control socket listening on {}", path.display());
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let app = app.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::SeqCst) {
+                println!("[Rust] Control socket stopped");
+                break;
+            }
+            match stream {
+                Ok(stream) => {
+                    let app = app.clone();
+                    thread::spawn(move || serve(&app, stream));
+                }
+                Err(e) => println!("[Warn] Control socket accept failed: {}", e),
+            }
+        }
+    });
+
+    Ok(stop)
+}
+
+fn serve(app: &AppHandle, stream: UnixStream) {
+    let mut writer = match stream.try_clone() {
+        Ok(w) => w,
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { return };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match serde_json::from_str::<Command>(&line) {
+            Ok(Command::Subscribe) => {
+                subscribe(app, writer);
+                return;
+            }
+            Ok(command) => dispatch(app, command),
+            Err(e) => Err(format!("Invalid command: {}", e)),
+        };
+
+        let reply = match reply {
+            Ok(data) => json!({ "ok": true, "data": data }),
+            Err(e) => json!({ "ok": false, "error": e }),
+        };
+        if writeln!(writer, "{}", reply).is_err() {
+            return;
+        }
+    }
+}
+
+fn dispatch(app: &AppHandle, command: Command) -> Result<Value, String> {
+    let state = app.state::<AppState>();
+    let recording = state.session.lock().unwrap().is_some();
+
+    match command {
+        Command::Start { profile } => {
+            start_with_profile(app, profile.as_deref()).map(Value::String)
+        }
+        Command::Stop => stop_recording(app),
+        Command::Toggle { .. } if recording => stop_recording(app),
+        Command::Toggle { profile } => {
+            start_with_profile(app, profile.as_deref()).map(Value::String)
+        }
+        Command::Cancel => {
+            handle_cancel(app);
+            Ok(Value::Null)
+        }
+        Command::Status => {
+            let session_id = state.session.lock().unwrap().as_ref().map(|s| s.id.clone());
+            let transcribing = state.processing_child.lock().unwrap().is_some();
+            let settings = state.settings.lock().unwrap().clone();
+            Ok(json!({
+                "recording": session_id.is_some(),
+                "session_id": session_id,
+                "transcribing": transcribing,
+                "active_profile": settings.active_profile,
+                "model": settings.model_type,
+            }))
+        }
+        Command::GetLastTranscript => Ok(json!(*state.last_transcript.lock().unwrap())),
+        Command::Subscribe => unreachable!("handled by serve"),
+    }
+}
+
+/// Same as a push-to-talk release, but refuses when nothing is recording.
+fn stop_recording(app: &AppHandle) -> Result<Value, String> {
+    if app.state::<AppState>().session.lock().unwrap().is_none() {
+        return Err("Not recording".into());
+    }
+    tauri::async_runtime::block_on(stop_and_deliver(app)).map(Value::String)
+}
+
+/// Forwards app events to the connection until a write fails.
+fn subscribe(app: &AppHandle, mut writer: UnixStream) {
+    let (tx, rx) = mpsc::channel::<(&'static str, String)>();
+    let ids: Vec<_> = EVENTS
+        .iter()
+        .map(|&name| {
+            let tx = tx.clone();
+            app.listen_any(name, move |event| {
+                let _ = tx.send((name, event.payload().to_string()));
+            })
+        })
+        .collect();
+
+    for (name, payload) in rx {
+        let payload: Value = serde_json::from_str(&payload).unwrap_or(Value::String(payload));
+        let line = json!({ "event": name, "payload": payload });
+        if writeln!(writer, "{}", line).is_err() {
+            break;
+        }
+    }
+
+    for id in ids {
+        app.unlisten(id);
+    }
+}
+
+/// `whisper-flow ctl ...`: sends one command to the running app and prints the
+/// reply (or, for `subscribe`, every event line). `None` if `args` isn't a `ctl`
+/// invocation, otherwise the exit code.
+pub fn client(args: impl IntoIterator<Item = String>) -> Option<i32> {
+    let mut args = args.into_iter();
+    if args.next().as_deref() != Some("ctl") {
+        return None;
+    }
+
+    let request = match parse_client_args(args) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, CTL_USAGE);
+            return Some(2);
+        }
+    };
+
+    Some(match send(&request) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    })
+}
+
+fn parse_client_args(mut args: impl Iterator<Item = String>) -> Result<Value, String> {
+    let cmd = args.next().ok_or("No command given")?;
+    let mut request = json!({ "cmd": cmd });
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" => {
+                let name = args.next().ok_or("Missing value for --profile")?;
+                request["profile"] = json!(name);
+            }
+            _ => return Err(format!("Unknown option: {}", arg)),
+        }
+    }
+
+    Ok(request)
+}
+
+/// Prints reply lines until the app closes the connection; `subscribe` streams.
+/// Returns whether the command succeeded.
+fn send(request: &Value) -> Result<bool, String> {
+    let path = socket_path();
+    let mut stream = UnixStream::connect(&path).map_err(|e| {
+        format!(
+            "Cannot reach the app at {} (is it running?): {}",
+            path.display(),
+            e
+        )
+    })?;
+    writeln!(stream, "{}", request).map_err(|e| e.to_string())?;
+
+    let streaming = request["cmd"] == "subscribe";
+    for line in BufReader::new(stream).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if streaming {
+            println!("{}", line);
+            continue;
+        }
+
+        let reply: Value = serde_json::from_str(&line).map_err(|e| e.to_string())?;
+        if reply["ok"] != true {
+            eprintln!("Error: {}", reply["error"].as_str().unwrap_or("unknown"));
+            return Ok(false);
+        }
+        match &reply["data"] {
+            Value::Null => {}
+            Value::String(text) => println!("{}", text),
+            data => println!("{}", data),
+        }
+        return Ok(true);
+    }
+
+    if streaming {
+        Ok(true)
+    } else {
+        Err("The app closed the connection without replying".into())
+    }
+}
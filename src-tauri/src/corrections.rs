@@ -0,0 +1,340 @@
+//! Learns from transcript edits (`update_history_item`). Each edited session's
+//! whisper output is diffed word by word against the user's latest text, and
+//! substitutions made in at least `min_count` sessions are proposed as a
+//! replacement rule or as `vocabulary` for the whisper prompt. Nothing changes
+//! until a suggestion is accepted.
+//!
+//! Only short substitutions count (up to `MAX_TERM_WORDS` words a side);
+//! rewording a sentence isn't a term correction. Text is split on whitespace,
+//! so scripts written without spaces yield few suggestions.
+
+use crate::profiles::profile_options;
+use crate::session::{load_revisions, RevisionSource, TranscriptRevision};
+use crate::settings::modify_settings;
+use crate::state::AppState;
+use crate::types::{
+    AppSettings, CorrectionSuggestion, CorrectionTarget, PostProcessing, Replacement,
+    TermCorrection,
+};
+use crate::utils::get_recordings_dir;
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Manager};
+
+const MAX_TERM_WORDS: usize = 3;
+/// Word pairs compared per session at most, bounding memory on long transcripts.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Recurring corrections, most frequent first. Ones already applied or
+/// dismissed are left out.
+pub fn suggestions(app: &AppHandle, min_count: usize) -> Result<Vec<CorrectionSuggestion>, String> {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+
+    let mut found: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    let entries = std::fs::read_dir(get_recordings_dir(app)?).map_err(|e| e.to_string())?;
+    for dir in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+        let Some(id) = dir.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let revisions = load_revisions(&dir);
+        let Some((whisper, user)) = latest_edit(&revisions) else {
+            continue;
+        };
+        for change in term_changes(whisper, user) {
+            let sessions = found.entry(change).or_default();
+            // The same fix made several times in one transcript counts once
+            if sessions.last().map(String::as_str) != Some(id) {
+                sessions.push(id.to_string());
+            }
+        }
+    }
+
+    let mut suggestions: Vec<_> = found
+        .into_iter()
+        .filter(|(_, sessions)| sessions.len() >= min_count.max(1))
+        .map(|((find, replace), sessions)| {
+            let correction = TermCorrection { find, replace };
+            CorrectionSuggestion {
+                suggested_target: suggested_target(&correction),
+                correction,
+                count: sessions.len(),
+                sessions,
+            }
+        })
+        .filter(|s| {
+            !settings.dismissed_corrections.contains(&s.correction)
+                && !is_applied(&settings, &s.correction)
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.count.cmp(&a.count));
+    Ok(suggestions)
+}
+
+/// The whisper text and the user's final version of the last edit.
+fn latest_edit(revisions: &[TranscriptRevision]) -> Option<(&str, &str)> {
+    let user = revisions
+        .iter()
+        .rposition(|r| r.source == RevisionSource::User)?;
+    let whisper = revisions[..user]
+        .iter()
+        .rfind(|r| r.source == RevisionSource::Whisper)?;
+    Some((whisper.text.as_str(), revisions[user].text.as_str()))
+}
+
+/// Names and jargon (mixed case, digits, or several misheard words becoming
+/// one) are better taught to whisper; anything else is a plain replacement.
+fn suggested_target(correction: &TermCorrection) -> CorrectionTarget {
+    let term = &correction.replace;
+    let is_jargon = !term.contains(' ')
+        && (term.chars().skip(1).any(char::is_uppercase)
+            || term.chars().any(|c| c.is_ascii_digit())
+            || correction.find.contains(' '));
+    if is_jargon {
+        CorrectionTarget::Vocabulary
+    } else {
+        CorrectionTarget::Replacement
+    }
+}
+
+fn is_applied(settings: &AppSettings, correction: &TermCorrection) -> bool {
+    settings.vocabulary.contains(&correction.replace)
+        || settings
+            .profiles
+            .iter()
+            .filter_map(|p| p.options.post_processing.as_ref())
+            .flat_map(|pp| &pp.replacements)
+            .any(|r| r.find == correction.find && r.replace == correction.replace)
+}
+
+/// Applies a correction. Replacement rules go to `profile`, or to the active
+/// profile when none is given.
+pub fn accept(
+    app: &AppHandle,
+    correction: TermCorrection,
+    target: CorrectionTarget,
+    profile: Option<String>,
+) -> Result<(), String> {
+    match target {
+        CorrectionTarget::Vocabulary => modify_settings(app, |s| {
+            if !s.vocabulary.contains(&correction.replace) {
+                s.vocabulary.push(correction.replace.clone());
+            }
+        }),
+        CorrectionTarget::Replacement => {
+            let name = {
+                let state = app.state::<AppState>();
+                let settings = state.settings.lock().unwrap();
+                let name = profile
+                    .or_else(|| settings.active_profile.clone())
+                    .ok_or("Replacement rules belong to a profile; choose one")?;
+                profile_options(&settings, &name)
+                    .ok_or_else(|| format!("Unknown profile: {}", name))?;
+                name
+            };
+
+            let rule = Replacement {
+                // Case-only fixes should also catch the sentence-initial capital
+                ignore_case: correction.find.to_lowercase() == correction.replace.to_lowercase(),
+                whole_word: true,
+                find: correction.find,
+                replace: correction.replace,
+            };
+            modify_settings(app, |s| {
+                if let Some(profile) = s.profiles.iter_mut().find(|p| p.name == name) {
+                    profile
+                        .options
+                        .post_processing
+                        .get_or_insert_with(PostProcessing::default)
+                        .replacements
+                        .push(rule);
+                }
+            })
+        }
+    }
+}
+
+/// Hides a suggestion for good.
+pub fn dismiss(app: &AppHandle, correction: TermCorrection) -> Result<(), String> {
+    modify_settings(app, |s| {
+        if !s.dismissed_corrections.contains(&correction) {
+            s.dismissed_corrections.push(correction);
+        }
+    })
+}
+
+/// `prompt` followed by the vocabulary terms it doesn't mention yet.
+pub fn prompt_with_vocabulary(app: &AppHandle, prompt: &str) -> String {
+    let vocabulary = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .vocabulary
+        .clone();
+    let missing: Vec<_> = vocabulary
+        .iter()
+        .filter(|term| !term.is_empty() && !prompt.contains(term.as_str()))
+        .map(String::as_str)
+        .collect();
+    if missing.is_empty() {
+        return prompt.to_string();
+    }
+
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        missing.join(", ")
+    } else {
+        format!("{} {}", prompt, missing.join(", "))
+    }
+}
+
+/// Words with surrounding punctuation stripped, so "Tauri." matches "Tauri".
+fn words(text: &str) -> Vec<&str> {
+    text.split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Short word substitutions between whisper's text and the user's.
+fn term_changes(before: &str, after: &str) -> Vec<(String, String)> {
+    let (before, after) = (words(before), words(after));
+
+    // Edits are usually few; the shared ends are most of the text
+    let prefix = before
+        .iter()
+        .zip(&after)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let before = &before[prefix..before.len() - suffix];
+    let after = &after[prefix..after.len() - suffix];
+    if before.len() * after.len() > MAX_DIFF_CELLS {
+        return vec![];
+    }
+
+    diff_hunks(before, after)
+        .into_iter()
+        .filter(|(removed, added)| {
+            (1..=MAX_TERM_WORDS).contains(&removed.len())
+                && (1..=MAX_TERM_WORDS).contains(&added.len())
+        })
+        .map(|(removed, added)| (removed.join(" "), added.join(" ")))
+        .collect()
+}
+
+/// Runs of differing words (removed, added), via the longest common subsequence.
+fn diff_hunks<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(Vec<&'a str>, Vec<&'a str>)> {
+    let (n, m) = (a.len(), b.len());
+    // lcs[at(i, j)]: length of the LCS of a[i..] and b[j..]
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = if a[i] == b[j] {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let mut hunk = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            if !hunk.0.is_empty() || !hunk.1.is_empty() {
+                hunks.push(std::mem::take(&mut hunk));
+            }
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[at(i, j + 1)] >= lcs[at(i + 1, j)]) {
+            hunk.1.push(b[j]);
+            j += 1;
+        } else {
+            hunk.0.push(a[i]);
+            i += 1;
+        }
+    }
+    if !hunk.0.is_empty() || !hunk.1.is_empty() {
+        hunks.push(hunk);
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(find: &str, replace: &str) -> (String, String) {
+        (find.to_string(), replace.to_string())
+    }
+
+    fn target(find: &str, replace: &str) -> CorrectionTarget {
+        suggested_target(&TermCorrection {
+            find: find.to_string(),
+            replace: replace.to_string(),
+        })
+    }
+
+    #[test]
+    fn single_substitution() {
+        assert_eq!(
+            term_changes(
+                "I build apps with tory every day",
+                "I build apps with Tori every day"
+            ),
+            [change("tory", "Tori")]
+        );
+    }
+
+    #[test]
+    fn several_words_becoming_one() {
+        assert_eq!(
+            term_changes("we ship it with tow ree now", "we ship it with Tauri now"),
+            [change("tow ree", "Tauri")]
+        );
+    }
+
+    #[test]
+    fn punctuation_only_edits_are_not_corrections() {
+        assert!(term_changes("Hello, world", "Hello world.").is_empty());
+        assert!(term_changes("Well... okay", "Well, okay!").is_empty());
+    }
+
+    #[test]
+    fn long_rewrites_exceed_max_term_words() {
+        // Three words a side is still a term, four is a rewrite
+        assert_eq!(
+            term_changes("start a b c end", "start x y z end"),
+            [change("a b c", "x y z")]
+        );
+        assert!(term_changes("start a b c d end", "start w x y z end").is_empty());
+        assert!(term_changes("start a end", "start w x y z end").is_empty());
+    }
+
+    #[test]
+    fn hunks_are_split_by_common_words() {
+        let before = ["one", "two", "three", "four"];
+        let after = ["one", "2", "three", "4", "five"];
+        assert_eq!(
+            diff_hunks(&before, &after),
+            [(vec!["two"], vec!["2"]), (vec!["four"], vec!["4", "five"])]
+        );
+    }
+
+    #[test]
+    fn jargon_goes_to_the_vocabulary() {
+        assert_eq!(target("get hub", "GitHub"), CorrectionTarget::Vocabulary);
+        assert_eq!(target("gpt for", "GPT4"), CorrectionTarget::Vocabulary);
+        assert_eq!(target("kubernetes", "k8s"), CorrectionTarget::Vocabulary);
+        assert_eq!(target("tory", "Tori"), CorrectionTarget::Replacement);
+        assert_eq!(target("there", "their"), CorrectionTarget::Replacement);
+        assert_eq!(target("alot", "a lot"), CorrectionTarget::Replacement);
+    }
+}
@@ -0,0 +1,289 @@
+//! Optional encryption at rest for session folders (`AppSettings::encrypt_sessions`).
+//!
+//! Files are AES-256-GCM sealed in place: `WFENC1 | nonce | ciphertext+tag`.
+//! Readers go through `read`/`read_to_string`, which pass plaintext files
+//! through untouched, so encrypted and older plain sessions mix freely.
+//! Transcripts and metadata are written encrypted; audio stays plain while
+//! ffmpeg/whisper work on it and is sealed with the rest of the session once
+//! transcribed (see `storage::spawn_archive`). Later readers get a temporary
+//! decrypted copy (`storage::session_wav`) that is deleted when they are done.
+//!
+//! The 256-bit key lives in the OS keyring. Where there is none (e.g. headless
+//! Linux CI) it is kept in `<app_data_dir>/encryption.key` instead, or in the
+//! file named by `WHISPER_FLOW_KEY_FILE`.
+
+use crate::session::{META_FILE, REVISIONS_FILE, SEGMENTS_FILE, TRANSLATION_FILE};
+use crate::state::AppState;
+use crate::storage::{FLAC_FILE, OPUS_FILE, RAW_FILE, WAV_FILE};
+use crate::utils::get_recordings_dir;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const MAGIC: &[u8] = b"WFENC1";
+const KEYRING_SERVICE: &str = "whisper-flow";
+const KEYRING_USER: &str = "session-encryption-key";
+
+/// Session files that are encrypted.
+pub const SESSION_FILES: &[&str] = &[
+    "transcript.txt",
+    TRANSLATION_FILE,
+    SEGMENTS_FILE,
+    META_FILE,
+    REVISIONS_FILE,
+    RAW_FILE,
+    WAV_FILE,
+    FLAC_FILE,
+    OPUS_FILE,
+];
+
+// Needed by code that has no AppHandle at hand (e.g. session.rs)
+static KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Loads the key (creating it when encryption is being turned on) and sets
+/// whether new files are written encrypted. An existing key is loaded even
+/// when encryption is off, so previously encrypted sessions stay readable.
+pub fn init(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let key = load_key(app, enabled)?;
+    if enabled && key.is_none() {
+        return Err("No encryption key available".into());
+    }
+
+    *KEY.lock().unwrap() = key;
+    ENABLED.store(enabled, Ordering::SeqCst);
+    Ok(())
+}
+
+fn load_key(app: &AppHandle, create: bool) -> Result<Option<[u8; 32]>, String> {
+    if let Some(path) = std::env::var_os("WHISPER_FLOW_KEY_FILE") {
+        return file_key(Path::new(&path), create);
+    }
+
+    let fallback = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("encryption.key");
+
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| e.to_string());
+    match entry.as_ref().map(|e| e.get_password()) {
+        Ok(Ok(hex)) => decode_key(&hex).map(Some),
+        // A key saved to the fallback while the keyring was unavailable wins
+        // over creating a new one, or its sessions would become unreadable
+        Ok(Err(keyring::Error::NoEntry)) if fallback.exists() => file_key(&fallback, false),
+        Ok(Err(keyring::Error::NoEntry)) if create => {
+            let key = generate_key()?;
+            match entry.as_ref().map(|e| e.set_password(&encode_key(&key))) {
+                Ok(Ok(())) => Ok(Some(key)),
+                _ => {
+                    println!("[Warn] Keyring unavailable, storing the key in a file");
+                    write_key_file(&fallback, &key).map(|()| Some(key))
+                }
+            }
+        }
+        Ok(Err(keyring::Error::NoEntry)) => Ok(None),
+        _ => {
+            println!("[Warn] Keyring unavailable, using {}", fallback.display());
+            file_key(&fallback, create)
+        }
+    }
+}
+
+fn file_key(path: &Path, create: bool) -> Result<Option<[u8; 32]>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(hex) => decode_key(hex.trim()).map(Some),
+        Err(_) if create => {
+            let key = generate_key()?;
+            write_key_file(path, &key)?;
+            Ok(Some(key))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+fn write_key_file(path: &Path, key: &[u8; 32]) -> Result<(), String> {
+    std::fs::write(path, encode_key(key)).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn generate_key() -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| "Failed to generate an encryption key".to_string())?;
+    Ok(key)
+}
+
+fn encode_key(key: &[u8; 32]) -> String {
+    key.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_key(hex: &str) -> Result<[u8; 32], String> {
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .filter_map(|i| hex.get(i..i + 2))
+        .filter_map(|pair| u8::from_str_radix(pair, 16).ok())
+        .collect();
+    bytes
+        .try_into()
+        .map_err(|_| "Stored encryption key is malformed".to_string())
+}
+
+fn cipher() -> Result<LessSafeKey, String> {
+    let key = KEY
+        .lock()
+        .unwrap()
+        .ok_or("File is encrypted but no key is available")?;
+    let unbound = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| "Invalid key".to_string())?;
+    Ok(LessSafeKey::new(unbound))
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Checks just the header, without reading the whole file.
+pub fn is_encrypted_file(path: &Path) -> Result<bool, String> {
+    let mut header = [0u8; MAGIC.len()];
+    let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    Ok(file.read_exact(&mut header).is_ok() && header == MAGIC)
+}
+
+fn seal(plain: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| "Failed to generate a nonce".to_string())?;
+
+    let mut body = plain.to_vec();
+    cipher()?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(MAGIC),
+            &mut body,
+        )
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + body.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+fn open(data: Vec<u8>) -> Result<Vec<u8>, String> {
+    if !is_encrypted(&data) {
+        return Ok(data);
+    }
+
+    let header = MAGIC.len() + NONCE_LEN;
+    if data.len() < header {
+        return Err("Encrypted file is truncated".into());
+    }
+    let nonce = Nonce::try_assume_unique_for_key(&data[MAGIC.len()..header])
+        .map_err(|_| "Invalid nonce".to_string())?;
+
+    let mut body = data[header..].to_vec();
+    let plain_len = cipher()?
+        .open_in_place(nonce, Aad::from(MAGIC), &mut body)
+        .map_err(|_| "Decryption failed (wrong key or corrupted file)".to_string())?
+        .len();
+    body.truncate(plain_len);
+    Ok(body)
+}
+
+/// Reads a session file, decrypting it if needed.
+pub fn read(path: &Path) -> Result<Vec<u8>, String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    open(data)
+}
+
+pub fn read_to_string(path: &Path) -> Result<String, String> {
+    String::from_utf8(read(path)?).map_err(|e| e.to_string())
+}
+
+/// Writes a session file, encrypted when encryption is on.
+pub fn write(path: &Path, data: impl AsRef<[u8]>) -> Result<(), String> {
+    let data = data.as_ref();
+    let body = if ENABLED.load(Ordering::SeqCst) {
+        seal(data)?
+    } else {
+        data.to_vec()
+    };
+    std::fs::write(path, body).map_err(|e| e.to_string())
+}
+
+/// Brings an existing file in line with the current setting. Returns whether it changed.
+fn convert_file(path: &Path, encrypt: bool) -> Result<bool, String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    if is_encrypted(&data) == encrypt {
+        return Ok(false);
+    }
+
+    let body = if encrypt { seal(&data)? } else { open(data)? };
+    // Temp file + rename, so an interrupted run never leaves a half-written file
+    let tmp = path.with_extension("crypt.tmp");
+    std::fs::write(&tmp, body).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Seals a session's files (audio included) if encryption is on.
+pub fn seal_session(session_dir: &Path) -> Result<usize, String> {
+    if !ENABLED.load(Ordering::SeqCst) {
+        return Ok(0);
+    }
+    convert_session(session_dir, true)
+}
+
+fn convert_session(session_dir: &Path, encrypt: bool) -> Result<usize, String> {
+    let mut changed = 0;
+    for name in SESSION_FILES {
+        let path = session_dir.join(name);
+        if path.exists() && convert_file(&path, encrypt)? {
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// Encrypts (or, with encryption off, decrypts) every existing session.
+/// Returns the number of files converted.
+pub async fn migrate(app: &AppHandle) -> Result<usize, String> {
+    let state = app.state::<AppState>();
+    let _job = state.transcription_lock.lock().await;
+    let in_use = state.sessions_in_use();
+    let encrypt = ENABLED.load(Ordering::SeqCst);
+
+    let dirs: Vec<PathBuf> = std::fs::read_dir(get_recordings_dir(app)?)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir() && !in_use.contains(path))
+        .collect();
+
+    let mut changed = 0;
+    for dir in dirs {
+        match convert_session(&dir, encrypt) {
+            Ok(n) => changed += n,
+            Err(e) => println!("[Warn] {}: {}", dir.display(), e),
+        }
+    }
+
+    println!(
+        "[Rust] {} {} session files",
+        if encrypt { "Encrypted" } else { "Decrypted" },
+        changed
+    );
+    Ok(changed)
+}
@@ -0,0 +1,347 @@
+//! `whisper-flow://` links, for notes apps and scripts:
+//!
+//! - `whisper-flow://record?profile=notes` starts recording (profile optional)
+//! - `whisper-flow://stop` stops and transcribes it
+//! - `whisper-flow://transcribe?file=/abs/path.m4a&profile=notes`
+//! - `whisper-flow://export?id=<session>&format=srt|vtt|txt&path=/abs/out.srt`
+//!
+//! Links are validated before anything runs; unknown actions or parameters are
+//! rejected. `transcribe` and `export` touch files the link names, so they go
+//! through the `deep_link_file_access` policy (a confirmation dialog by default).
+
+use crate::commands::history::export_transcript;
+use crate::profiles::profile_options;
+use crate::shortcut::{spawn_file_transcription, start_with_profile, stop_and_deliver};
+use crate::state::AppState;
+use crate::types::{DictationOptions, FileAccessPolicy};
+use crate::utils::{get_recordings_dir, is_valid_session_id};
+use std::collections::HashMap;
+use std::path::{Component, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+pub const SCHEME: &str = "whisper-flow";
+
+enum LinkAction {
+    Record {
+        profile: Option<String>,
+    },
+    Stop,
+    Transcribe {
+        file: PathBuf,
+        profile: Option<String>,
+    },
+    Export {
+        id: String,
+        format: String,
+        path: PathBuf,
+    },
+}
+
+/// Handles the link the app was launched with, then every link opened while it runs
+/// (forwarded by the single-instance plugin on Windows and Linux).
+pub fn init(app: &AppHandle) {
+    // Installed builds register the scheme at bundle time; this covers dev runs
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    if let Err(e) = app.deep_link().register_all() {
+        println!("[Warn] Failed to register {}:// links: {}", SCHEME, e);
+    }
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        open_urls(app, urls);
+    }
+
+    let handle = app.clone();
+    app.deep_link()
+        .on_open_url(move |event| open_urls(&handle, event.urls()));
+}
+
+fn open_urls(app: &AppHandle, urls: Vec<Url>) {
+    for url in urls {
+        let app = app.clone();
+        // Off the main thread: confirmation dialogs block
+        std::thread::spawn(move || {
+            println!("[Rust] Deep link: {}", url);
+            if let Err(e) = parse(&url).and_then(|action| run(&app, action)) {
+                println!("[Warn] Deep link {} failed: {}", url, e);
+                let _ = app.emit("transcription-error", e);
+            }
+        });
+    }
+}
+
+fn parse(url: &Url) -> Result<LinkAction, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("Not a {}:// link", SCHEME));
+    }
+
+    let mut params = HashMap::new();
+    for (key, value) in url.query_pairs() {
+        if params.insert(key.to_string(), value.to_string()).is_some() {
+            return Err(format!("Parameter given twice: {}", key));
+        }
+    }
+
+    let action = url.host_str().unwrap_or("");
+    let allowed: &[&str] = match action {
+        "record" => &["profile"],
+        "stop" => &[],
+        "transcribe" => &["file", "profile"],
+        "export" => &["id", "format", "path"],
+        _ => return Err(format!("Unknown action: {}", action)),
+    };
+    if let Some(key) = params.keys().find(|k| !allowed.contains(&k.as_str())) {
+        return Err(format!("Unknown parameter for {}: {}", action, key));
+    }
+
+    let mut take = |key: &str| params.remove(key).filter(|v| !v.is_empty());
+    let required = |value: Option<String>, key: &str| {
+        value.ok_or_else(|| format!("Missing parameter: {}", key))
+    };
+
+    Ok(match action {
+        "record" => LinkAction::Record {
+            profile: take("profile"),
+        },
+        "stop" => LinkAction::Stop,
+        "transcribe" => {
+            let file = absolute_path(&required(take("file"), "file")?)?;
+            if !file.is_file() {
+                return Err(format!("File not found: {}", file.display()));
+            }
+            LinkAction::Transcribe {
+                file,
+                profile: take("profile"),
+            }
+        }
+        "export" => {
+            let id = required(take("id"), "id")?;
+            if !is_valid_session_id(&id) {
+                return Err(format!("Invalid session id: {}", id));
+            }
+            let format = required(take("format"), "format")?;
+            if !["srt", "vtt", "txt"].contains(&format.as_str()) {
+                return Err(format!("Unsupported export format: {}", format));
+            }
+            let path = absolute_path(&required(take("path"), "path")?)?;
+            if !path.parent().is_some_and(|dir| dir.is_dir()) {
+                return Err(format!("Folder does not exist: {}", path.display()));
+            }
+            LinkAction::Export { id, format, path }
+        }
+        _ => unreachable!("checked above"),
+    })
+}
+
+/// Links come from other apps, so relative paths have nothing sensible to be
+/// relative to, and `..` has no business in a path a link names.
+fn absolute_path(value: &str) -> Result<PathBuf, String> {
+    let path = match Url::parse(value) {
+        Ok(url) if url.scheme() == "file" => url
+            .to_file_path()
+            .map_err(|_| format!("Invalid file URL: {}", value))?,
+        _ => PathBuf::from(value),
+    };
+    if !path.is_absolute() {
+        return Err(format!("Path must be absolute: {}", value));
+    }
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(format!("Path must not contain '..': {}", value));
+    }
+    Ok(path)
+}
+
+fn run(app: &AppHandle, action: LinkAction) -> Result<(), String> {
+    match action {
+        LinkAction::Record { profile } => start_with_profile(app, profile.as_deref()).map(|_| ()),
+        LinkAction::Stop => {
+            if app.state::<AppState>().session.lock().unwrap().is_none() {
+                return Err("Not recording".into());
+            }
+            // Errors are already reported by `stop_and_deliver`
+            let _ = tauri::async_runtime::block_on(stop_and_deliver(app));
+            Ok(())
+        }
+        LinkAction::Transcribe { file, profile } => {
+            let options = match profile.as_deref() {
+                Some(name) => {
+                    let state = app.state::<AppState>();
+                    let settings = state.settings.lock().unwrap();
+                    profile_options(&settings, name)
+                        .ok_or_else(|| format!("Unknown profile: {}", name))?
+                }
+                None => DictationOptions::default(),
+            };
+            confirm(
+                app,
+                &format!("A link wants to transcribe\n{}", file.display()),
+            )?;
+            spawn_file_transcription(app, file, options);
+            Ok(())
+        }
+        LinkAction::Export { id, format, path } => {
+            if !get_recordings_dir(app)?.join(&id).is_dir() {
+                return Err(format!("Unknown session: {}", id));
+            }
+            confirm(
+                app,
+                &format!("A link wants to export a transcript to\n{}", path.display()),
+            )?;
+            let path = path.to_string_lossy().to_string();
+            tauri::async_runtime::block_on(export_transcript(
+                app.clone(),
+                id,
+                format,
+                Some(path.clone()),
+                None,
+            ))?;
+            println!("[Rust] Deep link exported transcript to {}", path);
+            Ok(())
+        }
+    }
+}
+
+/// Applies the file-access policy; `Ask` blocks on a dialog.
+fn confirm(app: &AppHandle, message: &str) -> Result<(), String> {
+    let policy = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .deep_link_file_access;
+
+    let allowed = match policy {
+        FileAccessPolicy::Allow => true,
+        FileAccessPolicy::Deny => false,
+        FileAccessPolicy::Ask => app
+            .dialog()
+            .message(message)
+            .title("Whisper Flow")
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                "Allow".into(),
+                "Cancel".into(),
+            ))
+            .blocking_show(),
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err("File access from a link was not allowed".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(link: &str) -> Result<LinkAction, String> {
+        parse(&Url::parse(link).unwrap())
+    }
+
+    fn scratch_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("wf-link-{}-{}", std::process::id(), name));
+        std::fs::write(&path, b"audio").unwrap();
+        path
+    }
+
+    #[test]
+    fn parses_record_with_and_without_profile() {
+        assert!(matches!(
+            parse_str("whisper-flow://record"),
+            Ok(LinkAction::Record { profile: None })
+        ));
+        assert!(matches!(
+            parse_str("whisper-flow://record?profile=notes"),
+            Ok(LinkAction::Record { profile: Some(p) }) if p == "notes"
+        ));
+    }
+
+    #[test]
+    fn parses_stop() {
+        assert!(matches!(
+            parse_str("whisper-flow://stop"),
+            Ok(LinkAction::Stop)
+        ));
+    }
+
+    #[test]
+    fn parses_transcribe() {
+        let file = scratch_file("memo.m4a");
+        let link = format!(
+            "whisper-flow://transcribe?file={}&profile=notes",
+            file.display()
+        );
+        match parse_str(&link) {
+            Ok(LinkAction::Transcribe { file: f, profile }) => {
+                assert_eq!(f, file);
+                assert_eq!(profile.as_deref(), Some("notes"));
+            }
+            _ => panic!("expected a transcribe action"),
+        }
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn parses_export() {
+        let out = std::env::temp_dir().join("out.srt");
+        let link = format!(
+            "whisper-flow://export?id=2024-05-01_09-30-15-2&format=srt&path={}",
+            out.display()
+        );
+        match parse_str(&link) {
+            Ok(LinkAction::Export { id, format, path }) => {
+                assert_eq!(id, "2024-05-01_09-30-15-2");
+                assert_eq!(format, "srt");
+                assert_eq!(path, out);
+            }
+            _ => panic!("expected an export action"),
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_actions_and_parameters() {
+        assert!(parse_str("whisper-flow://delete?id=2024-05-01_09-30-15").is_err());
+        assert!(parse_str("https://record").is_err());
+        assert!(parse_str("whisper-flow://record?profile=a&profile=b").is_err());
+        assert!(parse_str("whisper-flow://stop?now=1").is_err());
+        assert!(parse_str("whisper-flow://transcribe").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_export_values() {
+        let dir = std::env::temp_dir();
+        let export = |id: &str, format: &str| {
+            parse_str(&format!(
+                "whisper-flow://export?id={}&format={}&path={}",
+                id,
+                format,
+                dir.join("out.txt").display()
+            ))
+        };
+        assert!(export("2024-05-01_09-30-15", "docx").is_err());
+        assert!(export("..", "txt").is_err());
+        assert!(export("%2E%2E%2Fother", "txt").is_err());
+        assert!(export("a%2Fb", "txt").is_err());
+    }
+
+    #[test]
+    fn rejects_path_traversal() {
+        let file = scratch_file("traversal.m4a");
+        let name = file.file_name().unwrap().to_string_lossy().to_string();
+
+        let relative = format!("whisper-flow://transcribe?file=../{}", name);
+        assert!(parse_str(&relative).is_err());
+
+        let dotted = file.parent().unwrap().join("x").join("..").join(&name);
+        let dotted = format!("whisper-flow://transcribe?file={}", dotted.display());
+        assert!(parse_str(&dotted).is_err());
+
+        let export = "whisper-flow://export?id=2024-05-01_09-30-15&format=txt&path=../out.txt";
+        assert!(parse_str(export).is_err());
+
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+//! Detects the application the user is dictating into, so a matching
+//! profile can be picked before transcription.
+//!
+//! - macOS: `NSWorkspace.frontmostApplication` (name + bundle id).
+//! - Linux: Hyprland (`hyprctl`), Sway (`swaymsg`), then X11 / XWayland via `xprop`.
+//!   Other Wayland compositors don't expose the focused window, so detection
+//!   returns `None` there.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FrontmostApp {
+    /// Localized app name (macOS) or window class / app id (Linux).
+    pub name: String,
+    pub bundle_id: Option<String>,
+    pub window_title: Option<String>,
+}
+
+#[cfg(target_os = "macos")]
+pub fn frontmost_app() -> Option<FrontmostApp> {
+    use objc2::rc::autoreleasepool;
+    use objc2_app_kit::NSWorkspace;
+
+    autoreleasepool(|_| unsafe {
+        let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+
+        Some(FrontmostApp {
+            name: app
+                .localizedName()
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+            bundle_id: app.bundleIdentifier().map(|b| b.to_string()),
+            window_title: None,
+        })
+    })
+}
+
+#[cfg(target_os = "linux")]
+pub fn frontmost_app() -> Option<FrontmostApp> {
+    linux::hyprland().or_else(linux::sway).or_else(linux::x11)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn frontmost_app() -> Option<FrontmostApp> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::FrontmostApp;
+    use std::process::Command;
+
+    fn run(program: &str, args: &[&str]) -> Option<String> {
+        let output = Command::new(program).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    pub fn hyprland() -> Option<FrontmostApp> {
+        std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+
+        let raw = run("hyprctl", &["activewindow", "-j"])?;
+        let json: serde_json::Value = serde_json::from_str(&raw).ok()?;
+
+        Some(FrontmostApp {
+            name: json["class"].as_str()?.to_string(),
+            bundle_id: None,
+            window_title: json["title"].as_str().map(str::to_string),
+        })
+    }
+
+    pub fn sway() -> Option<FrontmostApp> {
+        std::env::var_os("SWAYSOCK")?;
+
+        let raw = run("swaymsg", &["-t", "get_tree", "-r"])?;
+        let tree: serde_json::Value = serde_json::from_str(&raw).ok()?;
+        let node = find_focused(&tree)?;
+
+        // Native Wayland clients have `app_id`, XWayland ones a window class
+        let name = node["app_id"]
+            .as_str()
+            .or_else(|| node["window_properties"]["class"].as_str())?;
+
+        Some(FrontmostApp {
+            name: name.to_string(),
+            bundle_id: None,
+            window_title: node["name"].as_str().map(str::to_string),
+        })
+    }
+
+    fn find_focused(node: &serde_json::Value) -> Option<&serde_json::Value> {
+        if node["focused"].as_bool() == Some(true) {
+            return Some(node);
+        }
+
+        ["nodes", "floating_nodes"]
+            .iter()
+            .filter_map(|key| node[*key].as_array())
+            .flatten()
+            .find_map(find_focused)
+    }
+
+    pub fn x11() -> Option<FrontmostApp> {
+        std::env::var_os("DISPLAY")?;
+
+        // "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007"
+        let active = run("xprop", &["-root", "_NET_ACTIVE_WINDOW"])?;
+        let window_id = active.split('#').nth(1)?.trim().to_string();
+        if window_id == "0x0" {
+            return None;
+        }
+
+        // WM_CLASS(STRING) = "gnome-terminal-server", "Gnome-terminal"
+        // _NET_WM_NAME(UTF8_STRING) = "user@host: ~"
+        let props = run("xprop", &["-id", &window_id, "WM_CLASS", "_NET_WM_NAME"])?;
+        let mut name = None;
+        let mut title = None;
+
+        for line in props.lines() {
+            let value = match line.split_once(" = ") {
+                Some((_, v)) => v,
+                None => continue,
+            };
+
+            if line.starts_with("WM_CLASS") {
+                // The second string is the class; fall back to the instance name
+                let parts: Vec<&str> = value.split(", ").map(|p| p.trim_matches('"')).collect();
+                name = parts
+                    .get(1)
+                    .or_else(|| parts.first())
+                    .map(|s| s.to_string());
+            } else if line.starts_with("_NET_WM_NAME") {
+                title = Some(value.trim_matches('"').to_string());
+            }
+        }
+
+        Some(FrontmostApp {
+            name: name?,
+            bundle_id: None,
+            window_title: title,
+        })
+    }
+}
@@ -0,0 +1,297 @@
+//! Post-transcription hooks: hand each finished transcript to a user script or
+//! a webhook. Hooks run in the background, one after another, so a slow
+//! endpoint never holds up pasting. Every attempt is appended to the session's
+//! `hooks.jsonl`.
+//!
+//! Scripts get the transcript on stdin and the session in `WHISPER_FLOW_*`
+//! environment variables. The translation can outgrow the environment's size
+//! limit, so scripts get the path of a plain-text copy instead
+//! (`WHISPER_FLOW_TRANSLATION_FILE`). Webhooks receive the fields as a JSON body.
+
+use crate::crypto;
+use crate::session::{load_meta, TRANSLATION_FILE};
+use crate::state::AppState;
+use crate::storage::{plain_copy, PlainFile};
+use crate::types::{HookKind, TranscriptionHook};
+use crate::utils::get_recordings_dir;
+use chrono::Local;
+use serde::Serialize;
+use serde_json::json;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncWriteExt;
+
+pub const HOOKS_LOG_FILE: &str = "hooks.jsonl";
+
+#[derive(Serialize, Clone)]
+pub struct HookPayload {
+    pub session_id: String,
+    /// `dictation` or `file`.
+    pub source: &'static str,
+    pub text: String,
+    pub translation: Option<String>,
+    pub task: String,
+    pub language: Option<String>,
+    pub detected_language: Option<String>,
+    pub finished_at: String,
+    #[serde(skip)]
+    pub session_dir: PathBuf,
+}
+
+impl HookPayload {
+    fn from_session(
+        session_dir: &Path,
+        session_id: &str,
+        source: &'static str,
+        text: &str,
+    ) -> Self {
+        let meta = load_meta(session_dir);
+        HookPayload {
+            session_id: session_id.to_string(),
+            source,
+            text: text.to_string(),
+            translation: crypto::read_to_string(&session_dir.join(TRANSLATION_FILE)).ok(),
+            task: format!("{:?}", meta.task).to_lowercase(),
+            language: meta.language,
+            detected_language: meta.detected_language,
+            finished_at: Local::now().to_rfc3339(),
+            session_dir: session_dir.to_path_buf(),
+        }
+    }
+
+    /// Stand-in used by the settings "Test" button.
+    pub fn sample() -> Self {
+        HookPayload {
+            session_id: "test".into(),
+            source: "dictation",
+            text: "This is a test transcript from Whisper Flow.".into(),
+            translation: None,
+            task: "transcribe".into(),
+            language: Some("en".into()),
+            detected_language: None,
+            finished_at: Local::now().to_rfc3339(),
+            session_dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Readable copy of the session's translation, removed when dropped.
+    fn translation_file(&self) -> Result<Option<PlainFile>, String> {
+        if self.translation.is_none() {
+            return Ok(None);
+        }
+        plain_copy(&self.session_dir.join(TRANSLATION_FILE)).map(Some)
+    }
+
+    fn env_vars(&self, translation_file: Option<&Path>) -> Vec<(&'static str, String)> {
+        let optional = |v: &Option<String>| v.clone().unwrap_or_default();
+        vec![
+            ("WHISPER_FLOW_SESSION_ID", self.session_id.clone()),
+            (
+                "WHISPER_FLOW_SESSION_DIR",
+                self.session_dir.to_string_lossy().to_string(),
+            ),
+            ("WHISPER_FLOW_SOURCE", self.source.to_string()),
+            ("WHISPER_FLOW_TASK", self.task.clone()),
+            ("WHISPER_FLOW_LANGUAGE", optional(&self.language)),
+            (
+                "WHISPER_FLOW_DETECTED_LANGUAGE",
+                optional(&self.detected_language),
+            ),
+            (
+                "WHISPER_FLOW_TRANSLATION_FILE",
+                translation_file
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            ),
+            ("WHISPER_FLOW_FINISHED_AT", self.finished_at.clone()),
+        ]
+    }
+}
+
+/// Runs the enabled hooks for a finished session in the background.
+pub fn spawn_hooks(app: &AppHandle, session_id: &str, source: &'static str, text: &str) {
+    let hooks: Vec<_> = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .hooks
+        .iter()
+        .filter(|h| h.enabled)
+        .cloned()
+        .collect();
+    if hooks.is_empty() || text.is_empty() {
+        return;
+    }
+
+    let session_dir = match get_recordings_dir(app) {
+        Ok(dir) => dir.join(session_id),
+        Err(e) => {
+            println!("[Warn] Hooks skipped: {}", e);
+            return;
+        }
+    };
+    let payload = HookPayload::from_session(&session_dir, session_id, source, text);
+
+    tauri::async_runtime::spawn(async move {
+        for hook in hooks {
+            let _ = run_with_retries(&hook, &payload, Some(&session_dir)).await;
+        }
+    });
+}
+
+/// Runs `hook` until it succeeds or its retries are used up. With a
+/// `log_dir`, each attempt is appended to its hooks.jsonl.
+pub async fn run_with_retries(
+    hook: &TranscriptionHook,
+    payload: &HookPayload,
+    log_dir: Option<&Path>,
+) -> Result<String, String> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let started = Instant::now();
+        let result = run_once(hook, payload).await;
+
+        if let Some(dir) = log_dir {
+            log_attempt(dir, hook, attempt, started.elapsed(), &result);
+        }
+        match &result {
+            Ok(detail) => println!("[Rust] Hook '{}' succeeded: {}", hook.name, detail),
+            Err(e) => println!(
+                "[Warn] Hook '{}' failed (attempt {}): {}",
+                hook.name, attempt, e
+            ),
+        }
+
+        if result.is_ok() || attempt > hook.retries {
+            return result;
+        }
+        tokio::time::sleep(Duration::from_secs(1u64 << (attempt - 1).min(5))).await;
+    }
+}
+
+async fn run_once(hook: &TranscriptionHook, payload: &HookPayload) -> Result<String, String> {
+    let timeout = Duration::from_secs(hook.timeout_secs.max(1));
+    match hook.kind {
+        HookKind::Script => run_script(hook, payload, timeout).await,
+        HookKind::Webhook => post_webhook(hook, payload, timeout).await,
+    }
+}
+
+async fn run_script(
+    hook: &TranscriptionHook,
+    payload: &HookPayload,
+    timeout: Duration,
+) -> Result<String, String> {
+    if hook.command.is_empty() {
+        return Err("No command configured".into());
+    }
+
+    // Kept until the script has exited
+    let translation = payload.translation_file()?;
+    let mut child = tokio::process::Command::new(&hook.command)
+        .args(&hook.args)
+        .envs(payload.env_vars(translation.as_ref().map(|f| f.path())))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Dropping the child on timeout kills it
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", hook.command, e))?;
+
+    // Written separately so a script that never reads stdin can't block us
+    if let Some(mut stdin) = child.stdin.take() {
+        let text = payload.text.clone();
+        tokio::spawn(async move {
+            let _ = stdin.write_all(text.as_bytes()).await;
+        });
+    }
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| format!("Timed out after {}s", timeout.as_secs()))?
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(format!("exited with {}", output.status))
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!(
+            "exited with {}: {}",
+            output.status,
+            tail(stderr.trim())
+        ))
+    }
+}
+
+async fn post_webhook(
+    hook: &TranscriptionHook,
+    payload: &HookPayload,
+    timeout: Duration,
+) -> Result<String, String> {
+    if hook.url.is_empty() {
+        return Err("No URL configured".into());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client.post(&hook.url).json(payload);
+    for (name, value) in &hook.headers {
+        request = request.header(name, value);
+    }
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(format!("HTTP {}", status.as_u16()))
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(format!("HTTP {}: {}", status.as_u16(), tail(body.trim())))
+    }
+}
+
+/// Last few hundred characters of a script's stderr or an error body.
+fn tail(text: &str) -> &str {
+    let start = text
+        .char_indices()
+        .rev()
+        .nth(300)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    &text[start..]
+}
+
+fn log_attempt(
+    session_dir: &Path,
+    hook: &TranscriptionHook,
+    attempt: u32,
+    elapsed: Duration,
+    result: &Result<String, String>,
+) {
+    let (Ok(detail) | Err(detail)) = result;
+    let entry = json!({
+        "hook": hook.name,
+        "kind": hook.kind,
+        "attempt": attempt,
+        "ok": result.is_ok(),
+        "detail": detail,
+        "duration_ms": elapsed.as_millis() as u64,
+        "at": Local::now().to_rfc3339(),
+    });
+
+    let written = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(session_dir.join(HOOKS_LOG_FILE))
+        .and_then(|mut file| writeln!(file, "{}", entry));
+    if let Err(e) = written {
+        println!("[Warn] Failed to write hook log: {}", e);
+    }
+}
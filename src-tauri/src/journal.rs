@@ -0,0 +1,159 @@
+//! `OutputMode::Journal`: appends each transcript to a dated Markdown note,
+//! e.g. an Obsidian daily note.
+//!
+//! Templates may use `{date}` (2024-05-01), `{time}` (14:03), `{datetime}`,
+//! `{year}`, `{month}`, `{day}`, `{weekday}`, `{tags}` (`journal, voice`, for
+//! YAML) and `{hashtags}` (`#journal #voice`); the entry template also gets
+//! `{text}`. The path pattern only uses the date fields.
+
+use crate::state::AppState;
+use crate::types::JournalSettings;
+use chrono::{DateTime, Local};
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Attempts before giving up on a note another program keeps locked or replacing.
+const WRITE_ATTEMPTS: u32 = 5;
+
+/// Appends `text` to today's note and returns the note's path.
+pub fn append(app: &AppHandle, text: &str) -> Result<PathBuf, String> {
+    let settings = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .journal
+        .clone();
+    let now = Local::now();
+
+    let path = note_path(app, &settings, &now)?;
+    let frontmatter = render(&settings.frontmatter, &settings, &now);
+    let entry = render(&settings.entry_template, &settings, &now).replace("{text}", text.trim());
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    let mut attempt = 1;
+    loop {
+        match append_once(&path, &frontmatter, &entry) {
+            Ok(()) => {
+                println!("[Rust] Journal entry added to {}", path.display());
+                return Ok(path);
+            }
+            Err(e) if attempt < WRITE_ATTEMPTS => {
+                println!("[Warn] Journal write failed ({}), retrying", e);
+                std::thread::sleep(Duration::from_millis(200 * attempt as u64));
+                attempt += 1;
+            }
+            Err(e) => return Err(format!("Failed to write {}: {}", path.display(), e)),
+        }
+    }
+}
+
+fn note_path(
+    app: &AppHandle,
+    settings: &JournalSettings,
+    now: &DateTime<Local>,
+) -> Result<PathBuf, String> {
+    let pattern = render(&settings.path_pattern, settings, now);
+    let path = match pattern.strip_prefix("~/") {
+        Some(rest) => app.path().home_dir().map_err(|e| e.to_string())?.join(rest),
+        None => PathBuf::from(pattern),
+    };
+    if !path.is_absolute() {
+        return Err(format!("Journal path must be absolute: {}", path.display()));
+    }
+
+    // Vaults are often symlinked; write through the link rather than replacing it
+    Ok(std::fs::canonicalize(&path).unwrap_or(path))
+}
+
+fn render(template: &str, settings: &JournalSettings, now: &DateTime<Local>) -> String {
+    let hashtags: Vec<_> = settings.tags.iter().map(|t| format!("#{}", t)).collect();
+
+    template
+        .replace("{datetime}", &now.format("%Y-%m-%d %H:%M").to_string())
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H:%M").to_string())
+        .replace("{year}", &now.format("%Y").to_string())
+        .replace("{month}", &now.format("%m").to_string())
+        .replace("{day}", &now.format("%d").to_string())
+        .replace("{weekday}", &now.format("%A").to_string())
+        .replace("{tags}", &settings.tags.join(", "))
+        .replace("{hashtags}", &hashtags.join(" "))
+}
+
+/// One append, written with a single `write` on an `O_APPEND` handle so readers
+/// (and editors watching the file) never see half an entry, and text an editor
+/// saved in the meantime is never overwritten.
+fn append_once(path: &Path, frontmatter: &str, entry: &str) -> std::io::Result<()> {
+    // `create_new` makes sure only one writer ever adds the frontmatter
+    let (mut file, created) = match OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create_new(true)
+        .open(path)
+    {
+        Ok(file) => (file, true),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => (
+            OpenOptions::new().read(true).append(true).open(path)?,
+            false,
+        ),
+        Err(e) => return Err(e),
+    };
+
+    let mut body = String::new();
+    if created {
+        body.push_str(frontmatter);
+    }
+    body.push_str(separator(&mut file, &body)?);
+    body.push_str(entry);
+    if !body.ends_with('\n') {
+        body.push('\n');
+    }
+
+    file.write_all(body.as_bytes())?;
+    file.sync_data()?;
+    ensure_not_replaced(path, &file)
+}
+
+/// Newlines needed so the entry starts after a blank line.
+fn separator(file: &mut File, pending: &str) -> std::io::Result<&'static str> {
+    let len = file.metadata()?.len();
+    let mut tail = Vec::new();
+    if len > 0 {
+        file.seek(SeekFrom::Start(len.saturating_sub(2)))?;
+        file.read_to_end(&mut tail)?;
+    }
+    tail.extend_from_slice(pending.as_bytes());
+
+    Ok(if tail.is_empty() || tail.ends_with(b"\n\n") {
+        ""
+    } else if tail.ends_with(b"\n") {
+        "\n"
+    } else {
+        "\n\n"
+    })
+}
+
+/// Editors that save by writing a new file and renaming it over the note can
+/// do so between our open and write, leaving the entry in the orphaned file.
+/// Reported as an error so `append` retries against the new file.
+#[cfg(unix)]
+fn ensure_not_replaced(path: &Path, file: &File) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    if std::fs::metadata(path)?.ino() != file.metadata()?.ino() {
+        return Err(std::io::Error::other("note was replaced while writing"));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn ensure_not_replaced(_path: &Path, _file: &File) -> std::io::Result<()> {
+    Ok(())
+}
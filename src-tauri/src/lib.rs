@@ -1,12 +1,35 @@
+use std::collections::HashMap;
 use std::sync::Mutex;
-use tauri::{AppHandle, Emitter, Manager, WindowEvent};
-use tauri_plugin_global_shortcut::{Shortcut, ShortcutEvent, ShortcutState};
+use tauri::{Manager, WindowEvent};
 
+pub mod api;
+pub mod backup;
+pub mod chunking;
+pub mod cli;
+#[cfg(target_os = "macos")]
+pub mod clipboard;
 pub mod commands;
 pub mod consts;
+#[cfg(unix)]
+pub mod control;
+pub mod corrections;
+pub mod crypto;
+pub mod deeplink;
+pub mod frontmost;
+pub mod hooks;
+pub mod journal;
+pub mod profiles;
+pub mod session;
+pub mod settings;
+pub mod shortcut;
+pub mod source;
 pub mod state;
+pub mod storage;
+pub mod transcript;
+pub mod trash;
 pub mod types;
 pub mod utils;
+pub mod whisper;
 
 use crate::state::AppState;
 
@@ -15,8 +38,79 @@ use crate::state::AppState;
 // -----------------------------
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
-        .setup(|app| {
+    // `whisper-flow ctl ...` talks to the running instance; see control.rs
+    #[cfg(unix)]
+    if let Some(code) = control::client(std::env::args().skip(1)) {
+        std::process::exit(code);
+    }
+
+    // `whisper-flow transcribe ...` runs headless; see cli.rs
+    let cli_job = match cli::parse_args(std::env::args().skip(1)) {
+        Some(Ok(job)) => Some(job),
+        Some(Err(e)) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+        None => None,
+    };
+
+    let mut builder = tauri::Builder::default();
+
+    // A second launch (e.g. from a `whisper-flow://` link on Windows/Linux) hands
+    // its link to the running instance. Headless CLI runs stay independent.
+    #[cfg(desktop)]
+    if cli_job.is_none() {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|_app, argv, _cwd| {
+            println!("[Rust] Second instance started with {:?}", argv);
+        }));
+    }
+
+    builder
+        .setup(move |app| {
+            let settings = settings::load_settings(app.handle());
+            let shortcuts = settings.shortcuts.clone();
+            let encrypt_sessions = settings.encrypt_sessions;
+            app.manage(AppState {
+                session: Mutex::new(None),
+                processing_child: Mutex::new(None),
+                settings: Mutex::new(settings),
+                push_to_talk_pressed_at: Mutex::new(None),
+                shortcut_bindings: Mutex::new(HashMap::new()),
+                pending_options: Mutex::new(None),
+                last_transcript: Mutex::new(None),
+                transcription_lock: tokio::sync::Mutex::new(()),
+                queued_dictations: Mutex::new(Vec::new()),
+                api_server: Mutex::new(None),
+                control_socket: Mutex::new(None),
+            });
+
+            // Before anything reads or writes a session
+            if let Err(e) = crypto::init(app.handle(), encrypt_sessions) {
+                println!("[Warn] Session encryption unavailable: {}", e);
+            }
+
+            if let Some(job) = cli_job {
+                #[cfg(target_os = "macos")]
+                app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+                cli::run(app.handle().clone(), job);
+                return Ok(());
+            }
+
+            // Restore the persisted shortcut map (failures are logged per binding)
+            shortcut::register_shortcut_bindings(app.handle(), &shortcuts);
+
+            if let Err(e) = api::restart(app.handle()) {
+                println!("[Warn] {}", e);
+            }
+
+            deeplink::init(app.handle());
+            storage::spawn_cleanup_task(app.handle());
+
+            #[cfg(unix)]
+            if let Err(e) = control::restart(app.handle()) {
+                println!("[Warn] {}", e);
+            }
+
             let win = app.get_webview_window("recording-hint");
             if let Some(window) = win {
                 #[cfg(target_os = "macos")]
@@ -52,41 +146,13 @@ pub fn run() {
         })
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
-                .with_handler(
-                    |app: &AppHandle, _shortcut: &Shortcut, event: ShortcutEvent| {
-                        if event.state == ShortcutState::Pressed {
-                            use std::sync::{Mutex, OnceLock};
-                            use std::time::{Duration, Instant};
-
-                            // 使用靜態變數記錄上次觸發時間，實現後端防抖 (Debounce)
-                            static LAST_TRIGGER: OnceLock<Mutex<Option<Instant>>> = OnceLock::new();
-                            let mutex = LAST_TRIGGER.get_or_init(|| Mutex::new(None));
-
-                            let mut last_trigger = mutex.lock().unwrap();
-                            let now = Instant::now();
-
-                            if let Some(last) = *last_trigger {
-                                if now.duration_since(last) < Duration::from_millis(300) {
-                                    println!("[Rust] Shortcut ignored (Debounced)");
-                                    return;
-                                }
-                            }
-
-                            *last_trigger = Some(now);
-                            println!("[Rust] Shortcut triggered");
-                            let _ = app.emit("shortcut-event", "toggle-recording");
-                        }
-                    },
-                )
+                .with_handler(shortcut::handle_shortcut)
                 .build(),
         )
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
-        .manage(AppState {
-            session: Mutex::new(None),
-            processing_child: Mutex::new(None),
-        })
+        .plugin(tauri_plugin_deep_link::init())
         .invoke_handler(tauri::generate_handler![
             // Model commands
             commands::model::check_model_status,
@@ -96,18 +162,57 @@ pub fn run() {
             commands::audio::start_recording,
             commands::audio::stop_and_transcribe,
             commands::audio::transcribe_external_file,
+            commands::audio::transcribe_bytes,
             commands::audio::abort_transcription,
+            commands::audio::retranscribe_history_item,
+            commands::audio::cancel_recording,
             // System commands
             commands::system::check_accessibility_permission,
             commands::system::prompt_accessibility_permission,
             commands::system::request_microphone_permission,
             commands::system::update_global_shortcut,
+            commands::system::get_shortcut_bindings,
+            commands::system::set_shortcut_bindings,
             commands::system::get_recordings_dir_cmd,
             commands::system::open_recordings_dir,
             commands::system::open_accessibility_settings,
             // History commands
             commands::history::get_history,
             commands::history::delete_history_item,
+            commands::history::delete_history_items,
+            commands::history::get_trash,
+            commands::history::restore_history_item,
+            commands::history::purge_trash,
+            commands::history::update_history_item,
+            commands::history::get_history_revisions,
+            commands::history::update_history_details,
+            commands::corrections::get_correction_suggestions,
+            commands::corrections::accept_correction,
+            commands::corrections::dismiss_correction,
+            commands::history::rename_speaker,
+            commands::history::export_transcript,
+            commands::history::export_history,
+            commands::history::import_history,
+            commands::history::get_word_timings,
+            // Profile commands
+            commands::profile::get_profiles,
+            commands::profile::get_active_profile,
+            commands::profile::save_profile,
+            commands::profile::delete_profile,
+            commands::profile::set_active_profile,
+            commands::profile::get_app_profile_rules,
+            commands::profile::set_app_profile_rules,
+            commands::profile::get_frontmost_app,
+            // Settings commands
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::settings::test_hook,
+            // Storage commands
+            commands::storage::storage_usage,
+            commands::storage::run_storage_cleanup,
+            commands::storage::compress_recordings,
+            commands::storage::get_session_audio,
+            commands::storage::migrate_encryption,
             // Window commands
             commands::window::get_mouse_position
         ])
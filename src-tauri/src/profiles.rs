@@ -0,0 +1,290 @@
+use crate::frontmost::{frontmost_app, FrontmostApp};
+use crate::settings::modify_settings;
+use crate::state::AppState;
+use crate::types::{
+    AppProfileRule, AppSettings, DictationOptions, PostProcessing, Replacement, ShortcutBinding,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub fn profile_options(settings: &AppSettings, name: &str) -> Option<DictationOptions> {
+    settings
+        .profiles
+        .iter()
+        .find(|p| p.name == name)
+        .map(|p| p.options.clone())
+}
+
+/// Options of the active profile, or empty options when none is selected.
+pub fn active_profile_options(app: &AppHandle) -> DictationOptions {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().unwrap();
+
+    settings
+        .active_profile
+        .as_deref()
+        .and_then(|name| profile_options(&settings, name))
+        .unwrap_or_default()
+}
+
+/// Options of the first app rule matching the frontmost application,
+/// or empty options when no rule matches (or detection isn't available).
+pub fn frontmost_app_options(app: &AppHandle) -> DictationOptions {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    if settings.app_profile_rules.is_empty() {
+        return DictationOptions::default();
+    }
+
+    let front = match frontmost_app() {
+        Some(f) => f,
+        None => return DictationOptions::default(),
+    };
+
+    let rule = settings
+        .app_profile_rules
+        .iter()
+        .find(|rule| rule_matches(rule, &front));
+
+    match rule {
+        Some(rule) => {
+            println!(
+                "[Rust] Frontmost app '{}' matched rule '{}', using profile '{}'",
+                front.name, rule.app, rule.profile
+            );
+            profile_options(&settings, &rule.profile).unwrap_or_default()
+        }
+        None => DictationOptions::default(),
+    }
+}
+
+fn rule_matches(rule: &AppProfileRule, front: &FrontmostApp) -> bool {
+    let contains =
+        |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+
+    let app_matches = contains(&front.name, &rule.app)
+        || front
+            .bundle_id
+            .as_deref()
+            .is_some_and(|id| contains(id, &rule.app));
+
+    let title_matches = match &rule.title_contains {
+        Some(needle) => front
+            .window_title
+            .as_deref()
+            .is_some_and(|title| contains(title, needle)),
+        None => true,
+    };
+
+    !rule.app.is_empty() && app_matches && title_matches
+}
+
+/// Options a shortcut binding contributes: its named profile, then its inline overrides.
+pub fn binding_options(app: &AppHandle, binding: &ShortcutBinding) -> DictationOptions {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().unwrap();
+
+    let named = match binding.profile_name.as_deref() {
+        Some(name) => profile_options(&settings, name).unwrap_or_else(|| {
+            println!("[Warn] Shortcut refers to unknown profile: {}", name);
+            DictationOptions::default()
+        }),
+        None => DictationOptions::default(),
+    };
+
+    match &binding.profile {
+        Some(inline) => named.merged_with(inline),
+        None => named,
+    }
+}
+
+/// Sets (or clears) the active profile and notifies the UI.
+pub fn switch_profile(app: &AppHandle, name: Option<String>) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().unwrap();
+        if let Some(name) = &name {
+            if !settings.profiles.iter().any(|p| &p.name == name) {
+                return Err(format!("Unknown profile: {}", name));
+            }
+        }
+    }
+
+    modify_settings(app, |s| s.active_profile = name.clone())?;
+    println!("[Rust] Active profile: {:?}", name);
+    let _ = app.emit("profile-changed", name);
+    Ok(())
+}
+
+/// Moves to the next profile in list order, wrapping around through "no profile".
+pub fn cycle_profile(app: &AppHandle) -> Result<(), String> {
+    let next = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().unwrap();
+        let current = settings
+            .active_profile
+            .as_deref()
+            .and_then(|name| settings.profiles.iter().position(|p| p.name == name));
+
+        match current {
+            Some(i) => settings.profiles.get(i + 1).map(|p| p.name.clone()),
+            None => settings.profiles.first().map(|p| p.name.clone()),
+        }
+    };
+
+    switch_profile(app, next)
+}
+
+pub fn apply_post_processing(text: &str, rules: &PostProcessing) -> String {
+    let mut text = text.to_string();
+
+    for rule in &rules.replacements {
+        if !rule.find.is_empty() {
+            text = apply_replacement(&text, rule);
+        }
+    }
+
+    if rules.remove_trailing_period {
+        let trimmed = text.trim_end();
+        if let Some(stripped) = trimmed
+            .strip_suffix('.')
+            .or_else(|| trimmed.strip_suffix('。'))
+        {
+            text = stripped.to_string();
+        }
+    }
+
+    text
+}
+
+fn apply_replacement(text: &str, rule: &Replacement) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut prev: Option<char> = None;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(len) = match_len(rest, &rule.find, rule.ignore_case) {
+            let next = rest[len..].chars().next();
+            let is_word_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+
+            if !rule.whole_word || (!is_word_char(prev) && !is_word_char(next)) {
+                out.push_str(&rule.replace);
+                prev = rest[..len].chars().last();
+                rest = &rest[len..];
+                continue;
+            }
+        }
+
+        out.push(c);
+        prev = Some(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    out
+}
+
+/// Byte length of the prefix of `haystack` matching `needle`, if it matches.
+fn match_len(haystack: &str, needle: &str, ignore_case: bool) -> Option<usize> {
+    if !ignore_case {
+        return haystack.starts_with(needle).then_some(needle.len());
+    }
+
+    let mut chars = haystack.char_indices();
+    for n in needle.chars() {
+        let (_, h) = chars.next()?;
+        if !h.to_lowercase().eq(n.to_lowercase()) {
+            return None;
+        }
+    }
+
+    Some(chars.next().map(|(i, _)| i).unwrap_or(haystack.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replacement(find: &str, replace: &str, ignore_case: bool, whole_word: bool) -> Replacement {
+        Replacement {
+            find: find.to_string(),
+            replace: replace.to_string(),
+            ignore_case,
+            whole_word,
+        }
+    }
+
+    fn front(name: &str, bundle_id: Option<&str>, window_title: Option<&str>) -> FrontmostApp {
+        FrontmostApp {
+            name: name.to_string(),
+            bundle_id: bundle_id.map(str::to_string),
+            window_title: window_title.map(str::to_string),
+        }
+    }
+
+    fn rule(app: &str, title_contains: Option<&str>) -> AppProfileRule {
+        AppProfileRule {
+            app: app.to_string(),
+            title_contains: title_contains.map(str::to_string),
+            profile: "Work".to_string(),
+        }
+    }
+
+    #[test]
+    fn whole_word_replacement_skips_parts_of_words() {
+        let rule = replacement("cat", "dog", false, true);
+        assert_eq!(
+            apply_replacement("cat, concat and cats. cat", &rule),
+            "dog, concat and cats. dog"
+        );
+    }
+
+    #[test]
+    fn replacement_anywhere_without_whole_word() {
+        let rule = replacement("cat", "dog", false, false);
+        assert_eq!(apply_replacement("concat", &rule), "condog");
+    }
+
+    #[test]
+    fn case_folded_replacement() {
+        let rule = replacement("github", "GitHub", true, true);
+        assert_eq!(
+            apply_replacement("Push to GITHUB or Github.", &rule),
+            "Push to GitHub or GitHub."
+        );
+        // Case-sensitive rules leave other spellings alone
+        let rule = replacement("github", "GitHub", false, true);
+        assert_eq!(apply_replacement("GITHUB github", &rule), "GITHUB GitHub");
+    }
+
+    #[test]
+    fn post_processing_runs_replacements_then_strips_the_period() {
+        let rules = PostProcessing {
+            replacements: vec![
+                replacement("", "ignored", false, false),
+                replacement("teh", "the", true, true),
+            ],
+            remove_trailing_period: true,
+        };
+        assert_eq!(apply_post_processing("Teh end. ", &rules), "the end");
+        assert_eq!(apply_post_processing("完成。", &rules), "完成");
+    }
+
+    #[test]
+    fn rules_match_app_name_bundle_id_and_title() {
+        let slack = front("Slack", Some("com.tinyspeck.slackmacgap"), Some("general"));
+        assert!(rule_matches(&rule("slack", None), &slack));
+        assert!(rule_matches(&rule("TINYSPECK", None), &slack));
+        assert!(rule_matches(&rule("slack", Some("GENERAL")), &slack));
+    }
+
+    #[test]
+    fn rules_that_must_not_match() {
+        let terminal = front("Terminal", None, Some("ssh prod"));
+        assert!(!rule_matches(&rule("slack", None), &terminal));
+        assert!(!rule_matches(&rule("terminal", Some("vim")), &terminal));
+        // An empty app pattern would otherwise match everything
+        assert!(!rule_matches(&rule("", None), &terminal));
+        assert!(!rule_matches(
+            &rule("terminal", Some("ssh")),
+            &front("Terminal", None, None)
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+//! Per-session files beyond the transcript text:
+//! `session.json` (metadata), `transcript.json` (timed segments) and
+//! `revisions.json` (whisper output and user edits of transcript.txt).
+
+use crate::crypto;
+use crate::transcript::Segment;
+use crate::types::{MediaSlice, WhisperTask};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const META_FILE: &str = "session.json";
+pub const SEGMENTS_FILE: &str = "transcript.json";
+/// English translation kept next to transcript.txt for `WhisperTask::Both`.
+pub const TRANSLATION_FILE: &str = "translation.txt";
+pub const REVISIONS_FILE: &str = "revisions.json";
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SessionMeta {
+    /// With `Translate`, transcript.txt is already English.
+    pub task: WhisperTask,
+    pub diarized: bool,
+    /// transcript.txt holds SRT rather than plain text.
+    pub with_timestamps: bool,
+    /// Max SRT cue length used when the transcript was rendered.
+    pub max_chars: Option<usize>,
+    /// Part of the source file input_16k.wav was cut from; segment times include its offset.
+    pub slice: MediaSlice,
+    /// Speaker label (`Speaker 1`) -> display name chosen by the user.
+    pub speakers: BTreeMap<String, String>,
+    /// Language passed to whisper (`auto` if detection didn't resolve one).
+    pub language: Option<String>,
+    /// Result of the `auto` detection pass, before the allow-list was applied.
+    pub detected_language: Option<String>,
+    pub language_probability: Option<f32>,
+    /// The detected language wasn't allow-listed, so `language` was chosen
+    /// from the allow-list instead.
+    pub language_fallback: bool,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    pub favorite: bool,
+    /// Set while transcript.txt holds a user edit rather than whisper output.
+    pub edited_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionSource {
+    Whisper,
+    User,
+}
+
+/// One version of transcript.txt.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TranscriptRevision {
+    pub source: RevisionSource,
+    pub text: String,
+    pub created_at: String,
+}
+
+/// Missing or unreadable metadata (e.g. sessions from older versions) yields defaults.
+pub fn load_meta(session_dir: &Path) -> SessionMeta {
+    crypto::read_to_string(&session_dir.join(META_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_meta(session_dir: &Path, meta: &SessionMeta) -> Result<(), String> {
+    let body = serde_json::to_string_pretty(meta).map_err(|e| e.to_string())?;
+    crypto::write(&session_dir.join(META_FILE), body)
+        .map_err(|e| format!("Failed to write session metadata: {}", e))
+}
+
+pub fn load_segments(session_dir: &Path) -> Option<Vec<Segment>> {
+    let raw = crypto::read_to_string(&session_dir.join(SEGMENTS_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+pub fn save_segments(session_dir: &Path, segments: &[Segment]) -> Result<(), String> {
+    let body = serde_json::to_string(segments).map_err(|e| e.to_string())?;
+    crypto::write(&session_dir.join(SEGMENTS_FILE), body)
+        .map_err(|e| format!("Failed to write segments: {}", e))
+}
+
+/// Oldest first; empty for sessions that were never edited.
+pub fn load_revisions(session_dir: &Path) -> Vec<TranscriptRevision> {
+    crypto::read_to_string(&session_dir.join(REVISIONS_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_revisions(session_dir: &Path, revisions: &[TranscriptRevision]) -> Result<(), String> {
+    let body = serde_json::to_string_pretty(revisions).map_err(|e| e.to_string())?;
+    crypto::write(&session_dir.join(REVISIONS_FILE), body)
+        .map_err(|e| format!("Failed to write revisions: {}", e))
+}
@@ -0,0 +1,82 @@
+use crate::state::AppState;
+use crate::types::{AppSettings, ShortcutAction};
+use crate::utils::ensure_dir;
+use serde::Deserialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+pub fn get_settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    ensure_dir(&app_dir)?;
+    Ok(app_dir.join("settings.json"))
+}
+
+/// Loads settings from disk, falling back to defaults when the file is
+/// missing or unreadable (e.g. written by an older version).
+pub fn load_settings(app: &AppHandle) -> AppSettings {
+    let path = match get_settings_path(app) {
+        Ok(p) => p,
+        Err(_) => return AppSettings::default(),
+    };
+
+    let raw: serde_json::Value = match std::fs::read_to_string(&path) {
+        Ok(raw) => match serde_json::from_str(&raw) {
+            Ok(value) => value,
+            Err(e) => {
+                println!("[Warn] Invalid settings.json, using defaults: {}", e);
+                return AppSettings::default();
+            }
+        },
+        Err(_) => return AppSettings::default(),
+    };
+
+    let mut settings = AppSettings::deserialize(&raw).unwrap_or_else(|e| {
+        println!("[Warn] Invalid settings.json, using defaults: {}", e);
+        AppSettings::default()
+    });
+    migrate_shortcut_mode(&raw, &mut settings);
+    settings
+}
+
+/// Settings from before shortcut bindings had a single `shortcut_mode`. A
+/// push-to-talk choice carries over to the primary (default) binding.
+fn migrate_shortcut_mode(raw: &serde_json::Value, settings: &mut AppSettings) {
+    if raw.get("shortcuts").is_some() || raw["shortcut_mode"] != "push_to_talk" {
+        return;
+    }
+    if let Some(binding) = settings
+        .shortcuts
+        .iter_mut()
+        .find(|b| b.action == ShortcutAction::Toggle)
+    {
+        binding.action = ShortcutAction::PushToTalk;
+        println!("[Rust] Migrated shortcut_mode push_to_talk to the shortcut bindings");
+    }
+}
+
+/// Writes settings via a temp file + rename so a crash never leaves a half-written file.
+pub fn save_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let path = get_settings_path(app)?;
+    let tmp_path = path.with_extension("json.tmp");
+
+    let body = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(&tmp_path, body).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp_path, &path).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Applies `f` to the in-memory settings and persists the result. Nothing is
+/// written when `f` left them as they were.
+pub fn modify_settings(app: &AppHandle, f: impl FnOnce(&mut AppSettings)) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut settings = state.settings.lock().unwrap();
+    let before = serde_json::to_value(&*settings).map_err(|e| e.to_string())?;
+    f(&mut settings);
+
+    // Commands that remember the options they were called with usually change nothing
+    if serde_json::to_value(&*settings).map_err(|e| e.to_string())? == before {
+        return Ok(());
+    }
+    save_settings(app, &settings)
+}
@@ -0,0 +1,345 @@
+use crate::commands::audio::{
+    begin_recording, deliver_transcript, discard_recording, finish_recording, transcribe_file,
+};
+use crate::profiles::{binding_options, cycle_profile, profile_options, switch_profile};
+use crate::source::MediaSource;
+use crate::state::AppState;
+use crate::types::{
+    DictationOptions, FileTranscribeOptions, MediaSlice, OutputMode, ShortcutAction,
+    ShortcutBinding, ShortcutBindingStatus,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+/// Replaces every registered global shortcut with `bindings`.
+///
+/// Each binding is registered independently: an unparsable accelerator, a
+/// duplicate of an earlier binding, or an OS-level registration failure only
+/// affects that binding and is reported in its status.
+pub fn register_shortcut_bindings(
+    app: &AppHandle,
+    bindings: &[ShortcutBinding],
+) -> Vec<ShortcutBindingStatus> {
+    let global_shortcut = app.global_shortcut();
+    let _ = global_shortcut.unregister_all();
+
+    let mut registered: HashMap<u32, ShortcutBinding> = HashMap::new();
+    let mut statuses = Vec::new();
+
+    for binding in bindings {
+        let result = Shortcut::from_str(&binding.shortcut)
+            .map_err(|e| format!("Invalid shortcut: {}", e))
+            .and_then(|shortcut| {
+                // Different spellings ("Shift+Alt+A" / "Alt+Shift+A") map to the same id
+                if let Some(existing) = registered.get(&shortcut.id()) {
+                    return Err(format!(
+                        "Conflicts with '{}' ({:?})",
+                        existing.shortcut, existing.action
+                    ));
+                }
+
+                global_shortcut
+                    .register(shortcut)
+                    .map_err(|e| e.to_string())?;
+                registered.insert(shortcut.id(), binding.clone());
+                Ok(())
+            });
+
+        if let Err(e) = &result {
+            println!(
+                "[Warn] Failed to register shortcut {} ({:?}): {}",
+                binding.shortcut, binding.action, e
+            );
+        }
+
+        statuses.push(ShortcutBindingStatus {
+            shortcut: binding.shortcut.clone(),
+            action: binding.action,
+            registered: result.is_ok(),
+            error: result.err(),
+        });
+    }
+
+    *app.state::<AppState>().shortcut_bindings.lock().unwrap() = registered;
+    statuses
+}
+
+/// Global shortcut entry point, registered on the plugin builder in `lib.rs`.
+pub fn handle_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    let binding = app
+        .state::<AppState>()
+        .shortcut_bindings
+        .lock()
+        .unwrap()
+        .get(&shortcut.id())
+        .cloned();
+
+    let binding = match binding {
+        Some(b) => b,
+        None => return,
+    };
+    let profile = binding_options(app, &binding);
+
+    match binding.action {
+        ShortcutAction::Toggle => handle_toggle(app, event.state, profile),
+        ShortcutAction::PushToTalk => handle_push_to_talk(app, event.state, profile),
+        _ if event.state != ShortcutState::Pressed => {}
+        ShortcutAction::Cancel => handle_cancel(app),
+        ShortcutAction::RepasteLast => handle_repaste_last(app, profile),
+        ShortcutAction::TranscribeClipboardFile => handle_transcribe_clipboard_file(app, profile),
+        ShortcutAction::SwitchProfile => {
+            let result = match binding.profile_name {
+                Some(name) => switch_profile(app, Some(name)),
+                None => cycle_profile(app),
+            };
+            if let Err(e) = result {
+                println!("[Warn] Failed to switch profile: {}", e);
+            }
+        }
+    }
+}
+
+/// Toggle mode: the frontend owns the start/stop logic, we only forward presses.
+fn handle_toggle(app: &AppHandle, key_state: ShortcutState, profile: DictationOptions) {
+    if key_state != ShortcutState::Pressed {
+        return;
+    }
+
+    // 使用靜態變數記錄上次觸發時間，實現後端防抖 (Debounce)
+    static LAST_TRIGGER: OnceLock<Mutex<Option<Instant>>> = OnceLock::new();
+    let mutex = LAST_TRIGGER.get_or_init(|| Mutex::new(None));
+
+    let mut last_trigger = mutex.lock().unwrap();
+    let now = Instant::now();
+
+    if let Some(last) = *last_trigger {
+        if now.duration_since(last) < Duration::from_millis(300) {
+            println!("[Rust] Shortcut ignored (Debounced)");
+            return;
+        }
+    }
+
+    *last_trigger = Some(now);
+
+    // If this press is going to start a recording, hand the binding's profile
+    // to the `start_recording` call the frontend is about to make.
+    let state = app.state::<AppState>();
+    if state.session.lock().unwrap().is_none() {
+        *state.pending_options.lock().unwrap() = Some(profile);
+    }
+
+    println!("[Rust] Shortcut triggered");
+    let _ = app.emit("shortcut-event", "toggle-recording");
+}
+
+/// Push-to-talk mode: key-down starts recording, key-up stops and transcribes.
+/// Progress is reported to the UI through `recording-state` events.
+fn handle_push_to_talk(app: &AppHandle, key_state: ShortcutState, profile: DictationOptions) {
+    let state = app.state::<AppState>();
+
+    match key_state {
+        ShortcutState::Pressed => {
+            let mut pressed_at = state.push_to_talk_pressed_at.lock().unwrap();
+
+            // Ignore key repeat, and don't hijack a recording started some other way
+            if pressed_at.is_some() || state.session.lock().unwrap().is_some() {
+                return;
+            }
+
+            let device_id = state.settings.lock().unwrap().device_id.clone();
+            match begin_recording(app, &device_id, profile) {
+                Ok(session_id) => {
+                    println!("[Rust] Push-to-talk started (session: {})", session_id);
+                    *pressed_at = Some(Instant::now());
+                    let _ = app.emit("recording-state", "recording");
+                }
+                Err(e) => {
+                    println!("[Rust] Push-to-talk failed to start: {}", e);
+                    let _ = app.emit("transcription-error", e);
+                }
+            }
+        }
+        ShortcutState::Released => {
+            let pressed_at = match state.push_to_talk_pressed_at.lock().unwrap().take() {
+                Some(t) => t,
+                None => return,
+            };
+
+            let held = pressed_at.elapsed();
+            let settings = state.settings.lock().unwrap().clone();
+            let app = app.clone();
+
+            if held < Duration::from_millis(settings.push_to_talk_min_hold_ms) {
+                println!("[Rust] Push-to-talk released after {:?}, discarding", held);
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = discard_recording(&app).await {
+                        println!("[Warn] Failed to discard recording: {}", e);
+                    }
+                    let _ = app.emit("recording-state", "cancelled");
+                });
+                return;
+            }
+
+            tauri::async_runtime::spawn(async move {
+                let _ = stop_and_deliver(&app).await;
+            });
+        }
+    }
+}
+
+/// Starts a recording the way a push-to-talk press does, with a profile picked by name.
+/// Used by automation (control socket, deep links).
+pub fn start_with_profile(app: &AppHandle, profile: Option<&str>) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let (device_id, options) = {
+        let settings = state.settings.lock().unwrap();
+        let options = match profile {
+            Some(name) => profile_options(&settings, name)
+                .ok_or_else(|| format!("Unknown profile: {}", name))?,
+            None => DictationOptions::default(),
+        };
+        (settings.device_id.clone(), options)
+    };
+
+    let session_id = begin_recording(app, &device_id, options)?;
+    let _ = app.emit("recording-state", "recording");
+    Ok(session_id)
+}
+
+/// Stops the active recording and transcribes it with the saved settings,
+/// reporting progress like a push-to-talk release.
+pub async fn stop_and_deliver(app: &AppHandle) -> Result<String, String> {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+
+    let _ = app.emit("recording-state", "transcribing");
+    let result = finish_recording(
+        app,
+        &settings.model_type,
+        &settings.language,
+        &settings.prompt,
+        settings.task,
+    )
+    .await;
+    emit_finished(app, result.clone());
+    result
+}
+
+/// Discards the active recording; if nothing is recording, aborts the running transcription.
+pub fn handle_cancel(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    *state.push_to_talk_pressed_at.lock().unwrap() = None;
+    state.pending_options.lock().unwrap().take();
+
+    if state.session.lock().unwrap().is_some() {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = discard_recording(&app).await {
+                println!("[Warn] Failed to discard recording: {}", e);
+            }
+            let _ = app.emit("recording-state", "cancelled");
+        });
+        return;
+    }
+
+    if let Some(child) = state.processing_child.lock().unwrap().take() {
+        let _ = child.kill();
+        println!("[Rust] Transcription aborted by shortcut.");
+    }
+}
+
+fn handle_repaste_last(app: &AppHandle, profile: DictationOptions) {
+    let last = app
+        .state::<AppState>()
+        .last_transcript
+        .lock()
+        .unwrap()
+        .clone();
+
+    match last {
+        Some(text) => {
+            deliver_transcript(app, &text, profile.output_mode.unwrap_or(OutputMode::Paste))
+        }
+        None => println!("[Rust] Nothing to re-paste yet"),
+    }
+}
+
+fn handle_transcribe_clipboard_file(app: &AppHandle, profile: DictationOptions) {
+    let file_path = match clipboard_file_path(app) {
+        Some(p) => p,
+        None => {
+            let _ = app.emit(
+                "transcription-error",
+                "Clipboard does not contain a media file",
+            );
+            return;
+        }
+    };
+
+    spawn_file_transcription(app, file_path, profile);
+}
+
+/// Transcribes a local file in the background with the saved settings and
+/// `profile`'s overrides; the transcript is copied unless the profile says otherwise.
+pub fn spawn_file_transcription(app: &AppHandle, file_path: PathBuf, profile: DictationOptions) {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    let app = app.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let _ = app.emit("recording-state", "transcribing");
+        let options = FileTranscribeOptions {
+            model_type: profile.model_type.unwrap_or(settings.model_type),
+            language: profile.language.unwrap_or(settings.language),
+            prompt: profile.prompt.unwrap_or(settings.prompt),
+            task: profile.task.unwrap_or(settings.task),
+            with_timestamps: settings.with_timestamps,
+            diarize: false,
+            max_chars: None,
+            slice: MediaSlice::default(),
+        };
+        let result = transcribe_file(
+            &app,
+            MediaSource::File(file_path.to_string_lossy().to_string()),
+            &options,
+            profile.output_mode.unwrap_or(OutputMode::Copy),
+        )
+        .await;
+        emit_finished(&app, result);
+    });
+}
+
+/// Reports the outcome of a backend-driven transcription to the UI.
+pub fn emit_finished(app: &AppHandle, result: Result<String, String>) {
+    match result {
+        Ok(_) => {
+            let _ = app.emit("recording-state", "done");
+        }
+        Err(e) => {
+            let _ = app.emit("transcription-error", e);
+        }
+    }
+}
+
+/// Resolves a file copied to the clipboard: a Finder file URL on macOS,
+/// otherwise a plain-text path or `file://` URL.
+fn clipboard_file_path(app: &AppHandle) -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    let text = crate::clipboard::file_url().or_else(|| app.clipboard().read_text().ok());
+    #[cfg(not(target_os = "macos"))]
+    let text = app.clipboard().read_text().ok();
+
+    let text = text?;
+    let text = text.trim();
+
+    let path = match Url::parse(text) {
+        Ok(url) if url.scheme() == "file" => url.to_file_path().ok()?,
+        _ => PathBuf::from(text),
+    };
+
+    path.is_file().then_some(path)
+}
@@ -0,0 +1,123 @@
+//! Inputs accepted for transcription. Whatever the origin, the media ends up
+//! as a path ffmpeg can read, and from there goes through the usual 16 kHz
+//! WAV conversion.
+
+use futures_util::StreamExt;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+pub enum MediaSource {
+    /// Local file.
+    File(String),
+    /// `http(s)://` URL, downloaded into the session folder first.
+    Url(String),
+    /// Media held in memory in any container ffmpeg can probe (browser drags, stdin).
+    Bytes(Vec<u8>),
+    /// Headerless 16 kHz mono s16le PCM, e.g. piped from `arecord` or `sox`.
+    RawPcm(Vec<u8>),
+}
+
+/// What ffmpeg should read for a source.
+pub struct PreparedInput {
+    /// Passed to `-i`.
+    pub path: String,
+    /// Demuxer options placed before `-i` (raw PCM has no header to probe).
+    pub format_args: Vec<String>,
+    /// Copy written into the session folder, removed once converted.
+    pub temp_file: Option<PathBuf>,
+}
+
+impl MediaSource {
+    /// `http://` and `https://` inputs are URLs, anything else a local path.
+    pub fn from_input(input: &str) -> Self {
+        let lower = input.to_ascii_lowercase();
+        if lower.starts_with("http://") || lower.starts_with("https://") {
+            MediaSource::Url(input.to_string())
+        } else {
+            MediaSource::File(input.to_string())
+        }
+    }
+
+    /// Short description for logs.
+    pub fn describe(&self) -> String {
+        match self {
+            MediaSource::File(path) => path.clone(),
+            MediaSource::Url(url) => url.clone(),
+            MediaSource::Bytes(data) => format!("<{} bytes>", data.len()),
+            MediaSource::RawPcm(data) => format!("<{} bytes of PCM>", data.len()),
+        }
+    }
+
+    /// Makes the source readable by ffmpeg, writing remote and in-memory
+    /// media to `session_dir` as needed.
+    pub async fn prepare(self, session_dir: &Path) -> Result<PreparedInput, String> {
+        match self {
+            MediaSource::File(path) => {
+                if !Path::new(&path).exists() {
+                    return Err(format!("File not found: {}", path));
+                }
+                Ok(PreparedInput {
+                    path,
+                    format_args: vec![],
+                    temp_file: None,
+                })
+            }
+            MediaSource::Url(url) => {
+                let target = session_dir.join("source.download");
+                download(&url, &target).await?;
+                Ok(temp_input(target, vec![]))
+            }
+            MediaSource::Bytes(data) => {
+                let target = session_dir.join("source.bin");
+                write(&target, &data).await?;
+                Ok(temp_input(target, vec![]))
+            }
+            MediaSource::RawPcm(data) => {
+                let target = session_dir.join("source.raw");
+                write(&target, &data).await?;
+                let format_args = ["-f", "s16le", "-ar", "16000", "-ac", "1"]
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect();
+                Ok(temp_input(target, format_args))
+            }
+        }
+    }
+}
+
+fn temp_input(path: PathBuf, format_args: Vec<String>) -> PreparedInput {
+    PreparedInput {
+        path: path.to_string_lossy().to_string(),
+        format_args,
+        temp_file: Some(path),
+    }
+}
+
+async fn write(path: &Path, data: &[u8]) -> Result<(), String> {
+    if data.is_empty() {
+        return Err("No audio data received".into());
+    }
+    tokio::fs::write(path, data)
+        .await
+        .map_err(|e| format!("Failed to write media: {}", e))
+}
+
+async fn download(url: &str, path: &Path) -> Result<(), String> {
+    println!("[Rust] Downloading {}", url);
+    let response = reqwest::get(url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let data = chunk.map_err(|e| e.to_string())?;
+        file.write_all(&data).await.map_err(|e| e.to_string())?;
+    }
+
+    file.flush().await.map_err(|e| e.to_string())
+}
@@ -1,8 +1,42 @@
-use crate::types::RecordingSession;
-use std::sync::Mutex;
+use crate::types::{AppSettings, DictationOptions, RecordingSession, ShortcutBinding};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tauri_plugin_shell::process::CommandChild;
 
 pub struct AppState {
     pub session: Mutex<Option<RecordingSession>>,
     pub processing_child: Mutex<Option<CommandChild>>,
+    pub settings: Mutex<AppSettings>,
+    /// Set while a push-to-talk shortcut is held down.
+    pub push_to_talk_pressed_at: Mutex<Option<Instant>>,
+    /// Currently registered bindings, keyed by `Shortcut::id()`.
+    pub shortcut_bindings: Mutex<HashMap<u32, ShortcutBinding>>,
+    /// Profile of a toggle shortcut, picked up by the next `start_recording`.
+    /// Cleared when a recording is stopped or cancelled, so it never goes stale.
+    pub pending_options: Mutex<Option<DictationOptions>>,
+    pub last_transcript: Mutex<Option<String>>,
+    /// Held for the duration of a transcription (dictation or file), so jobs queue up.
+    pub transcription_lock: tokio::sync::Mutex<()>,
+    /// Folders of stopped dictations still waiting for `transcription_lock`.
+    pub queued_dictations: Mutex<Vec<PathBuf>>,
+    /// Running HTTP API server, if enabled.
+    pub api_server: Mutex<Option<Arc<tiny_http::Server>>>,
+    /// Stop flag of the running control socket, if enabled.
+    pub control_socket: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl AppState {
+    /// Session folders that cleanup, archiving and the like must leave alone:
+    /// the one being recorded and dictations waiting to be transcribed.
+    pub fn sessions_in_use(&self) -> Vec<PathBuf> {
+        // Recording first: `finish_recording` moves the folder to the queue
+        // while holding the session lock, so it is never missed in between
+        let recording = self.session.lock().unwrap().as_ref().map(|s| s.dir.clone());
+        let mut dirs = self.queued_dictations.lock().unwrap().clone();
+        dirs.extend(recording);
+        dirs
+    }
 }
@@ -0,0 +1,559 @@
+//! Keeps the recordings folder from growing without bound. Per
+//! `AppSettings::storage`, a background task periodically:
+//!
+//! - deletes `input.raw` once a session has its WAV
+//! - deletes audio of sessions recorded more than `audio_retention_days` ago,
+//!   keeping the text
+//! - deletes audio of the least recently used sessions while the folder and
+//!   the trash are over `max_total_mb`, starting with sessions in the trash
+//! - compresses WAVs to `archive_format` (FLAC or Opus); `session_wav` decodes
+//!   them again when a session's audio is needed
+//! - encrypts finished sessions when `encrypt_sessions` is on (see `crypto`)
+//! - purges sessions deleted more than `trash_retention_days` ago (see `trash`)
+//!
+//! Transcripts and metadata are never removed here; that's what history delete is for.
+
+use crate::crypto;
+use crate::state::AppState;
+use crate::trash;
+use crate::types::{ArchiveReport, AudioArchive, StoragePolicy, StorageUsage};
+use crate::utils::{get_recordings_dir, get_trash_dir, parse_session_id};
+use chrono::{Local, TimeZone};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::ShellExt;
+
+pub const RAW_FILE: &str = "input.raw";
+pub const WAV_FILE: &str = "input_16k.wav";
+pub const FLAC_FILE: &str = "input.flac";
+pub const OPUS_FILE: &str = "input.opus";
+/// Prefix of the short-lived plain copies made by `plain_copy`.
+const PLAIN_PREFIX: &str = ".plain-";
+
+/// What a session file is, for usage reporting and eviction.
+#[derive(PartialEq, Eq)]
+enum FileKind {
+    Raw,
+    Audio,
+    Text,
+    Other,
+}
+
+const ALL_KINDS: [FileKind; 4] = [
+    FileKind::Raw,
+    FileKind::Audio,
+    FileKind::Text,
+    FileKind::Other,
+];
+
+fn classify(name: &str) -> FileKind {
+    match name {
+        RAW_FILE => FileKind::Raw,
+        WAV_FILE | FLAC_FILE | OPUS_FILE => FileKind::Audio,
+        // Leftovers of interrupted file transcriptions
+        _ if name.starts_with("chunk_") && name.ends_with(".wav") => FileKind::Audio,
+        _ if name.starts_with("source.") => FileKind::Audio,
+        _ if name.ends_with(".txt") || name.ends_with(".json") || name.ends_with(".jsonl") => {
+            FileKind::Text
+        }
+        _ => FileKind::Other,
+    }
+}
+
+struct SessionFiles {
+    dir: PathBuf,
+    files: Vec<(PathBuf, FileKind, u64)>,
+    /// When the session was recorded, from its id.
+    started: Option<SystemTime>,
+    /// Newest modification inside the session, i.e. when it was last recorded,
+    /// transcribed or edited.
+    last_used: SystemTime,
+}
+
+impl SessionFiles {
+    fn size(&self, kinds: &[FileKind]) -> u64 {
+        self.files
+            .iter()
+            .filter(|(_, kind, _)| kinds.contains(kind))
+            .map(|(_, _, size)| size)
+            .sum()
+    }
+
+    /// Deletes files of the given kinds, returning the bytes freed.
+    fn remove(&mut self, kinds: &[FileKind]) -> u64 {
+        let mut freed = 0;
+        self.files.retain(|(path, kind, size)| {
+            if !kinds.contains(kind) {
+                return true;
+            }
+            match fs::remove_file(path) {
+                Ok(()) => {
+                    freed += size;
+                    false
+                }
+                Err(e) => {
+                    println!("[Warn] Failed to remove {}: {}", path.display(), e);
+                    true
+                }
+            }
+        });
+        freed
+    }
+}
+
+fn scan(recordings_dir: &Path) -> Result<Vec<SessionFiles>, String> {
+    let mut sessions = Vec::new();
+
+    for entry in fs::read_dir(recordings_dir).map_err(|e| e.to_string())? {
+        let dir = entry.map_err(|e| e.to_string())?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let mut files = Vec::new();
+        let mut last_used = SystemTime::UNIX_EPOCH;
+        for file in fs::read_dir(&dir).map_err(|e| e.to_string())? {
+            let path = file.map_err(|e| e.to_string())?.path();
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            if let Ok(modified) = metadata.modified() {
+                last_used = last_used.max(modified);
+            }
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string();
+            files.push((path, classify(&name), metadata.len()));
+        }
+
+        let started = dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(parse_session_id)
+            .and_then(|(started, _)| Local.from_local_datetime(&started).earliest())
+            .map(SystemTime::from);
+        sessions.push(SessionFiles {
+            dir,
+            files,
+            started,
+            last_used,
+        });
+    }
+
+    Ok(sessions)
+}
+
+/// Sizes of everything in the recordings folder, by category, plus the trash.
+pub fn usage(app: &AppHandle) -> Result<StorageUsage, String> {
+    let sessions = scan(&get_recordings_dir(app)?)?;
+    let trash = scan(&get_trash_dir(app)?)?;
+
+    let mut usage = StorageUsage {
+        sessions: sessions.len(),
+        ..Default::default()
+    };
+    for session in &sessions {
+        usage.total_bytes += session.size(&ALL_KINDS);
+        usage.raw_bytes += session.size(&[FileKind::Raw]);
+        usage.audio_bytes += session.size(&[FileKind::Audio]);
+        usage.text_bytes += session.size(&[FileKind::Text]);
+        usage.other_bytes += session.size(&[FileKind::Other]);
+    }
+    usage.trash_bytes = trash.iter().map(|s| s.size(&ALL_KINDS)).sum();
+    usage.total_bytes += usage.trash_bytes;
+
+    Ok(usage)
+}
+
+/// Applies the policy once. Waits for any running transcription and leaves
+/// sessions being recorded or waiting to be transcribed alone.
+pub async fn enforce(app: &AppHandle) -> Result<u64, String> {
+    let state = app.state::<AppState>();
+    let _job = state.transcription_lock.lock().await;
+
+    let policy = state.settings.lock().unwrap().storage.clone();
+    let in_use = state.sessions_in_use();
+
+    let now = SystemTime::now();
+    let (mut sessions, busy): (Vec<_>, Vec<_>) = scan(&get_recordings_dir(app)?)?
+        .into_iter()
+        .partition(|s| !in_use.contains(&s.dir));
+    // Still counts towards the size cap
+    let in_use_bytes = busy.iter().map(|s| s.size(&ALL_KINDS)).sum();
+    let mut trash = scan(&get_trash_dir(app)?)?;
+
+    sessions.iter_mut().for_each(remove_stale_plain_copies);
+    let mut freed = apply(&policy, &mut sessions, &mut trash, in_use_bytes, now);
+    for session in &sessions {
+        match archive_session(app, &session.dir, policy.archive_format).await {
+            Ok(saved) => freed += saved,
+            Err(e) => println!("[Warn] {}", e),
+        }
+    }
+    if freed > 0 {
+        println!("[Rust] Storage cleanup freed {} KB", freed / 1024);
+    }
+    Ok(freed)
+}
+
+fn apply(
+    policy: &StoragePolicy,
+    sessions: &mut [SessionFiles],
+    trash: &mut [SessionFiles],
+    in_use_bytes: u64,
+    now: SystemTime,
+) -> u64 {
+    let mut freed = 0;
+    let audio = [FileKind::Raw, FileKind::Audio];
+
+    if policy.delete_raw_after_conversion {
+        for session in sessions.iter_mut() {
+            // The raw capture is the only audio until the WAV exists
+            if session.dir.join(WAV_FILE).exists() {
+                freed += session.remove(&[FileKind::Raw]);
+            }
+        }
+    }
+
+    if policy.audio_retention_days > 0 {
+        let max_age = Duration::from_secs(policy.audio_retention_days.saturating_mul(24 * 60 * 60));
+        for session in sessions.iter_mut() {
+            // Editing a transcript doesn't make the recording any newer; the
+            // mtime is only a guess for folders not named like a session
+            let recorded = session.started.unwrap_or(session.last_used);
+            let age = now.duration_since(recorded).unwrap_or_default();
+            if age > max_age {
+                freed += session.remove(&audio);
+            }
+        }
+    }
+
+    if policy.max_total_mb > 0 {
+        let cap = policy.max_total_mb.saturating_mul(1024 * 1024);
+        let mut total: u64 = in_use_bytes
+            + sessions.iter().map(|s| s.size(&ALL_KINDS)).sum::<u64>()
+            + trash.iter().map(|s| s.size(&ALL_KINDS)).sum::<u64>();
+
+        // Deleted sessions give up their audio before any in the history
+        trash.sort_by_key(|s| s.last_used);
+        sessions.sort_by_key(|s| s.last_used);
+        for session in trash.iter_mut().chain(sessions.iter_mut()) {
+            if total <= cap {
+                break;
+            }
+            let removed = session.remove(&audio);
+            total -= removed;
+            freed += removed;
+        }
+    }
+
+    freed
+}
+
+/// Deletes the raw capture right after conversion when the policy asks for it.
+pub fn after_conversion(app: &AppHandle, raw_path: &Path) {
+    let policy = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .storage
+        .clone();
+    if policy.delete_raw_after_conversion {
+        if let Err(e) = fs::remove_file(raw_path) {
+            println!("[Warn] Failed to remove {}: {}", raw_path.display(), e);
+        }
+    }
+}
+
+/// Runs `enforce` and empties expired trash at startup, then every `cleanup_interval_mins`.
+pub fn spawn_cleanup_task(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = enforce(&app).await {
+                println!("[Warn] Storage cleanup failed: {}", e);
+            }
+
+            let policy = app
+                .state::<AppState>()
+                .settings
+                .lock()
+                .unwrap()
+                .storage
+                .clone();
+            if let Err(e) = trash::purge_expired(&app, policy.trash_retention_days) {
+                println!("[Warn] Trash cleanup failed: {}", e);
+            }
+
+            let minutes = policy.cleanup_interval_mins.max(1);
+            tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+        }
+    });
+}
+
+fn archive_file(format: AudioArchive) -> Option<(&'static str, &'static [&'static str])> {
+    match format {
+        AudioArchive::Wav => None,
+        AudioArchive::Flac => Some((FLAC_FILE, &["-c:a", "flac", "-f", "flac"])),
+        // Plenty for 16 kHz mono speech
+        AudioArchive::Opus => Some((OPUS_FILE, &["-c:a", "libopus", "-b:a", "24k", "-f", "ogg"])),
+    }
+}
+
+/// Replaces a session's WAV (and raw capture) with a compressed copy, then
+/// encrypts the session if `encrypt_sessions` is on. Returns the bytes saved;
+/// sessions without a WAV keep their audio as it is.
+pub async fn archive_session(
+    app: &AppHandle,
+    session_dir: &Path,
+    format: AudioArchive,
+) -> Result<u64, String> {
+    let saved = compress_session(app, session_dir, format).await?;
+    crypto::seal_session(session_dir)?;
+    Ok(saved)
+}
+
+async fn compress_session(
+    app: &AppHandle,
+    session_dir: &Path,
+    format: AudioArchive,
+) -> Result<u64, String> {
+    let Some((name, codec_args)) = archive_file(format) else {
+        return Ok(0);
+    };
+    let wav = session_dir.join(WAV_FILE);
+    if !wav.exists() {
+        return Ok(0);
+    }
+    let input = plain_copy(&wav)?;
+
+    let raw = session_dir.join(RAW_FILE);
+    let before = file_size(&wav) + file_size(&raw);
+    let target = session_dir.join(name);
+    let part = session_dir.join(format!("{}.part", name));
+
+    let mut args = vec![
+        "-y".to_string(),
+        "-i".into(),
+        input.path().to_string_lossy().to_string(),
+    ];
+    args.extend(codec_args.iter().map(|a| a.to_string()));
+    args.push(part.to_string_lossy().to_string());
+    run_ffmpeg(app, args).await?;
+
+    fs::rename(&part, &target).map_err(|e| e.to_string())?;
+    for path in [wav, raw] {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+        }
+    }
+    // An archive in the previous format is now redundant
+    for other in [FLAC_FILE, OPUS_FILE] {
+        if other != name {
+            let _ = fs::remove_file(session_dir.join(other));
+        }
+    }
+
+    Ok(before.saturating_sub(file_size(&target)))
+}
+
+/// A plain file for tools that read from disk (ffmpeg, whisper). Temporary
+/// copies are removed when this is dropped, so decrypted audio only exists
+/// on disk while it is being read.
+pub struct PlainFile {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl PlainFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn temporary(session_dir: &Path, name: &str) -> Self {
+        PlainFile {
+            path: session_dir.join(format!("{}{}", PLAIN_PREFIX, name)),
+            temporary: true,
+        }
+    }
+}
+
+impl Drop for PlainFile {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// `path` itself if it isn't encrypted, otherwise a decrypted temporary copy.
+pub fn plain_copy(path: &Path) -> Result<PlainFile, String> {
+    if !crypto::is_encrypted_file(path)? {
+        return Ok(PlainFile {
+            path: path.to_path_buf(),
+            temporary: false,
+        });
+    }
+
+    let dir = path.parent().ok_or("Invalid session file path")?;
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid session file path")?;
+    let copy = PlainFile::temporary(dir, name);
+    fs::write(copy.path(), crypto::read(path)?).map_err(|e| e.to_string())?;
+    Ok(copy)
+}
+
+/// The session's 16 kHz WAV. An encrypted WAV is decrypted to a temporary
+/// copy; compressed audio is decoded to one. Either is deleted once the
+/// returned `PlainFile` is dropped, and the stored audio is left as it was.
+pub async fn session_wav(app: &AppHandle, session_dir: &Path) -> Result<PlainFile, String> {
+    let wav = session_dir.join(WAV_FILE);
+    if wav.exists() {
+        return plain_copy(&wav);
+    }
+
+    let archived = [FLAC_FILE, OPUS_FILE]
+        .iter()
+        .map(|name| session_dir.join(name))
+        .find(|path| path.exists())
+        .ok_or("Session has no audio")?;
+    let input = plain_copy(&archived)?;
+
+    println!("[Rust] Decoding {}", archived.display());
+    let decoded = PlainFile::temporary(session_dir, WAV_FILE);
+    run_ffmpeg(
+        app,
+        [
+            "-y".to_string(),
+            "-i".into(),
+            input.path().to_string_lossy().to_string(),
+            "-ar".into(),
+            "16000".into(),
+            "-ac".into(),
+            "1".into(),
+            "-c:a".into(),
+            "pcm_s16le".into(),
+            "-f".into(),
+            "wav".into(),
+            decoded.path().to_string_lossy().to_string(),
+        ],
+    )
+    .await?;
+
+    Ok(decoded)
+}
+
+/// Removes plain copies left behind by a crash.
+fn remove_stale_plain_copies(session: &mut SessionFiles) {
+    session.files.retain(|(path, _, _)| {
+        let stale = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(PLAIN_PREFIX));
+        if stale {
+            if let Err(e) = fs::remove_file(path) {
+                println!("[Warn] Failed to remove {}: {}", path.display(), e);
+            }
+        }
+        !stale
+    });
+}
+
+/// Compresses and/or encrypts a finished session in the background, per the settings.
+pub fn spawn_archive(app: &AppHandle, session_dir: PathBuf) {
+    let (format, encrypt) = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().unwrap();
+        (settings.storage.archive_format, settings.encrypt_sessions)
+    };
+    if format == AudioArchive::Wav && !encrypt {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        // Not while a transcription may be reading this session
+        let state = app.state::<AppState>();
+        let _job = state.transcription_lock.lock().await;
+        if let Err(e) = archive_session(&app, &session_dir, format).await {
+            println!("[Warn] {}", e);
+        }
+    });
+}
+
+/// One-off migration: compresses every finished session to `format`.
+pub async fn compress_all(app: &AppHandle, format: AudioArchive) -> Result<ArchiveReport, String> {
+    if format == AudioArchive::Wav {
+        return Err("Choose FLAC or Opus to compress to".into());
+    }
+
+    let state = app.state::<AppState>();
+    let _job = state.transcription_lock.lock().await;
+    let in_use = state.sessions_in_use();
+
+    let mut report = ArchiveReport::default();
+    for session in scan(&get_recordings_dir(app)?)? {
+        // No transcript yet means a dictation is still being transcribed
+        if in_use.contains(&session.dir) || !session.dir.join("transcript.txt").exists() {
+            continue;
+        }
+        match archive_session(app, &session.dir, format).await {
+            Ok(0) => {}
+            Ok(saved) => {
+                report.sessions += 1;
+                report.saved_bytes += saved;
+            }
+            Err(e) => {
+                println!("[Warn] {}: {}", session.dir.display(), e);
+                report.failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "[Rust] Compressed {} sessions, saved {} KB ({} failed)",
+        report.sessions,
+        report.saved_bytes / 1024,
+        report.failed
+    );
+    Ok(report)
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Runs ffmpeg without claiming `processing_child`: archiving happens in the
+/// background and must not be what a cancel kills.
+async fn run_ffmpeg<I>(app: &AppHandle, args: I) -> Result<(), String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let output = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| e.to_string())?
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "FFmpeg failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
+}
@@ -0,0 +1,374 @@
+//! Structured transcripts: parsing whisper-cli's full JSON output (`-ojf`)
+//! and rendering segments to plain text / SRT / VTT.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// tinydiarize marks speaker turns but can't tell more than two speakers apart.
+pub const MAX_DIARIZED_SPEAKERS: usize = 2;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Segment {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    /// Speaker label (e.g. `Speaker 1`) when the file was diarized.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<Word>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Word {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    /// Mean token probability reported by whisper.
+    pub probability: f32,
+}
+
+// --- whisper-cli `-ojf` output (only the fields we use) ---
+
+#[derive(Deserialize)]
+struct WhisperJson {
+    #[serde(default)]
+    transcription: Vec<WhisperSegment>,
+}
+
+#[derive(Deserialize)]
+struct WhisperSegment {
+    offsets: WhisperOffsets,
+    text: String,
+    /// Set by tinydiarize (`-tdrz`) when the next segment starts a new speaker turn.
+    #[serde(default)]
+    speaker_turn_next: bool,
+    #[serde(default)]
+    tokens: Vec<WhisperToken>,
+}
+
+#[derive(Deserialize)]
+struct WhisperToken {
+    text: String,
+    offsets: WhisperOffsets,
+    #[serde(default)]
+    p: f32,
+    /// DTW-aligned start in 10 ms units, or -1 when `-dtw` wasn't used.
+    #[serde(default = "no_dtw")]
+    t_dtw: i64,
+}
+
+fn no_dtw() -> i64 {
+    -1
+}
+
+#[derive(Deserialize)]
+struct WhisperOffsets {
+    from: i64,
+    to: i64,
+}
+
+/// Parses whisper-cli's JSON output into segments.
+///
+/// With `speaker_count = Some(n)` speaker labels are derived from tinydiarize
+/// turn markers. tinydiarize only detects *turns*, not identities, so each
+/// turn is assumed to hand over to the other speaker of a two-person
+/// conversation: labels alternate between `Speaker 1` and `Speaker 2`. More
+/// speakers can't be told apart, so `n` is capped at 2; users fix labels up by
+/// renaming speakers.
+pub fn parse_whisper_json(raw: &str, speaker_count: Option<usize>) -> Result<Vec<Segment>, String> {
+    let json: WhisperJson =
+        serde_json::from_str(raw).map_err(|e| format!("Invalid whisper JSON: {}", e))?;
+
+    let mut speaker = 0;
+    let mut segments = Vec::new();
+
+    for seg in json.transcription {
+        let text = seg.text.trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+
+        segments.push(Segment {
+            start_ms: seg.offsets.from,
+            end_ms: seg.offsets.to,
+            text,
+            speaker: speaker_count.map(|_| format!("Speaker {}", speaker + 1)),
+            words: words_from_tokens(&seg.tokens),
+        });
+
+        if let Some(count) = speaker_count {
+            if seg.speaker_turn_next {
+                speaker = (speaker + 1) % count.clamp(1, MAX_DIARIZED_SPEAKERS);
+            }
+        }
+    }
+
+    Ok(segments)
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+    )
+}
+
+/// Groups BPE tokens into words: a token starting with a space opens a new
+/// word, anything else continues the current one. Special tokens
+/// (`[_BEG_]`, `[_TT_150]`, ...) are skipped.
+///
+/// With DTW a token runs from its aligned time to the next token's, so both
+/// ends come from the same timing source; otherwise both come from `offsets`.
+fn words_from_tokens(tokens: &[WhisperToken]) -> Vec<Word> {
+    let tokens: Vec<&WhisperToken> = tokens
+        .iter()
+        .filter(|t| !(t.text.starts_with("[_") && t.text.ends_with(']')))
+        .collect();
+
+    let mut words: Vec<Word> = Vec::new();
+    let mut token_counts: Vec<usize> = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        let (start_ms, end_ms) = if token.t_dtw >= 0 {
+            let start = token.t_dtw * 10;
+            let end = match tokens.get(i + 1) {
+                Some(next) if next.t_dtw >= 0 => next.t_dtw * 10,
+                // Last token of the segment
+                _ => token.offsets.to,
+            };
+            (start, end.max(start))
+        } else {
+            (token.offsets.from, token.offsets.to)
+        };
+
+        // CJK has no spaces between words, so each ideograph/kana is its own word
+        let starts_word = token.text.starts_with(' ') || token.text.starts_with(is_cjk);
+
+        match words.last_mut() {
+            Some(word) if !starts_word && !word.text.ends_with(is_cjk) => {
+                word.text.push_str(&token.text);
+                word.end_ms = end_ms;
+                word.probability += token.p;
+                *token_counts.last_mut().unwrap() += 1;
+            }
+            _ => {
+                words.push(Word {
+                    start_ms,
+                    end_ms,
+                    text: token.text.clone(),
+                    probability: token.p,
+                });
+                token_counts.push(1);
+            }
+        }
+    }
+
+    for (word, count) in words.iter_mut().zip(token_counts) {
+        word.text = word.text.trim().to_string();
+        word.probability /= count as f32;
+    }
+
+    words.retain(|w| !w.text.is_empty());
+    words
+}
+
+/// Re-cuts segments into cues of at most `max_chars` characters, breaking only
+/// between words. Segments without word timings are kept as they are.
+pub fn split_segments(segments: &[Segment], max_chars: usize) -> Vec<Segment> {
+    let mut out = Vec::new();
+
+    for segment in segments {
+        if segment.words.is_empty() || segment.text.chars().count() <= max_chars {
+            out.push(segment.clone());
+            continue;
+        }
+
+        let mut chunk: Vec<Word> = Vec::new();
+
+        for word in &segment.words {
+            chunk.push(word.clone());
+            if chunk.len() > 1 && join_words(&chunk).chars().count() > max_chars {
+                let next = chunk.pop().unwrap();
+                out.push(cue_from_words(segment, std::mem::take(&mut chunk)));
+                chunk.push(next);
+            }
+        }
+
+        if !chunk.is_empty() {
+            out.push(cue_from_words(segment, chunk));
+        }
+    }
+
+    out
+}
+
+fn cue_from_words(parent: &Segment, words: Vec<Word>) -> Segment {
+    Segment {
+        start_ms: words.first().map(|w| w.start_ms).unwrap_or(parent.start_ms),
+        end_ms: words.last().map(|w| w.end_ms).unwrap_or(parent.end_ms),
+        text: join_words(&words),
+        speaker: parent.speaker.clone(),
+        words,
+    }
+}
+
+/// Joins words with spaces, except between CJK characters.
+fn join_words(words: &[Word]) -> String {
+    let mut text = String::new();
+    for word in words {
+        let cjk_boundary = text.ends_with(is_cjk) || word.text.starts_with(is_cjk);
+        if !text.is_empty() && !cjk_boundary {
+            text.push(' ');
+        }
+        text.push_str(&word.text);
+    }
+    text
+}
+
+/// Display name for a segment's speaker, honoring per-session renames.
+fn speaker_name<'a>(segment: &'a Segment, names: &'a BTreeMap<String, String>) -> Option<&'a str> {
+    let label = segment.speaker.as_deref()?;
+    Some(names.get(label).map(String::as_str).unwrap_or(label))
+}
+
+/// Plain text. Diarized transcripts get one `Name: text` paragraph per speaker turn.
+pub fn to_text(segments: &[Segment], names: &BTreeMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut current: Option<&str> = None;
+
+    for segment in segments {
+        match speaker_name(segment, names) {
+            Some(name) if current != Some(name) => {
+                if !out.is_empty() {
+                    out.push_str("\n\n");
+                }
+                out.push_str(name);
+                out.push_str(": ");
+                current = Some(name);
+            }
+            Some(_) => out.push(' '),
+            None if !out.is_empty() => out.push('\n'),
+            None => {}
+        }
+        out.push_str(&segment.text);
+    }
+
+    out
+}
+
+pub fn to_srt(segments: &[Segment], names: &BTreeMap<String, String>) -> String {
+    let mut out = String::new();
+
+    for (i, segment) in segments.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n",
+            i + 1,
+            format_timestamp(segment.start_ms, ','),
+            format_timestamp(segment.end_ms, ',')
+        ));
+        match speaker_name(segment, names) {
+            Some(name) => out.push_str(&format!("[{}] {}\n\n", name, segment.text)),
+            None => out.push_str(&format!("{}\n\n", segment.text)),
+        }
+    }
+
+    out
+}
+
+pub fn to_vtt(segments: &[Segment], names: &BTreeMap<String, String>) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+
+    for segment in segments {
+        out.push_str(&format!(
+            "{} --> {}\n",
+            format_timestamp(segment.start_ms, '.'),
+            format_timestamp(segment.end_ms, '.')
+        ));
+        // WebVTT voice spans carry the speaker name
+        match speaker_name(segment, names) {
+            Some(name) => out.push_str(&format!("<v {}>{}\n\n", name, segment.text)),
+            None => out.push_str(&format!("{}\n\n", segment.text)),
+        }
+    }
+
+    out
+}
+
+/// `HH:MM:SS,mmm` (SRT) or `HH:MM:SS.mmm` (VTT).
+fn format_timestamp(ms: i64, separator: char) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(text: &str, from: i64, to: i64, t_dtw: i64) -> WhisperToken {
+        WhisperToken {
+            text: text.to_string(),
+            offsets: WhisperOffsets { from, to },
+            p: 1.0,
+            t_dtw,
+        }
+    }
+
+    fn timings(words: &[Word]) -> Vec<(&str, i64, i64)> {
+        words
+            .iter()
+            .map(|w| (w.text.as_str(), w.start_ms, w.end_ms))
+            .collect()
+    }
+
+    #[test]
+    fn dtw_words_end_where_the_next_token_starts() {
+        // Offsets lag well behind the DTW times, as they do in practice
+        let tokens = [
+            token("[_BEG_]", 0, 0, 0),
+            token(" Hello", 0, 900, 20),
+            token(" wor", 900, 1000, 55),
+            token("ld", 1000, 1100, 70),
+            token(" again", 1100, 1200, 95),
+        ];
+
+        let words = words_from_tokens(&tokens);
+        assert_eq!(
+            timings(&words),
+            [
+                ("Hello", 200, 550),
+                ("world", 550, 950),
+                ("again", 950, 1200)
+            ]
+        );
+        for pair in words.windows(2) {
+            assert!(pair[0].start_ms <= pair[0].end_ms);
+            assert!(pair[0].end_ms <= pair[1].start_ms);
+        }
+    }
+
+    #[test]
+    fn last_dtw_token_never_ends_before_it_starts() {
+        let tokens = [token(" Hi", 0, 300, 50)];
+        assert_eq!(timings(&words_from_tokens(&tokens)), [("Hi", 500, 500)]);
+    }
+
+    #[test]
+    fn without_dtw_both_ends_come_from_offsets() {
+        let tokens = [token(" Hello", 0, 400, -1), token(" there", 400, 900, -1)];
+        assert_eq!(
+            timings(&words_from_tokens(&tokens)),
+            [("Hello", 0, 400), ("there", 400, 900)]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Deleting a session moves it to `<app_data_dir>/trash/<id>_<millis>/`, with
+//! `.trashed.json` noting its id and when it was deleted. It can be restored
+//! from there until the storage cleanup purges it (`trash_retention_days`) or
+//! the trash is emptied.
+
+use crate::crypto;
+use crate::session::load_meta;
+use crate::state::AppState;
+use crate::types::{TrashItem, TrashReport};
+use crate::utils::{get_recordings_dir, get_trash_dir, is_valid_session_id, next_free_session_id};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+const MARKER_FILE: &str = ".trashed.json";
+
+#[derive(Serialize, Deserialize)]
+struct TrashMarker {
+    id: String,
+    deleted_at: String,
+}
+
+/// `root/<name>` if it is a real folder directly inside `root`. Names are
+/// validated first, so `../models` and the like never reach the filesystem.
+fn folder_in(root: &Path, name: &str) -> Result<PathBuf, String> {
+    if !is_valid_session_id(name) {
+        return Err(format!("Invalid session id: {}", name));
+    }
+    let dir = root.join(name);
+    // Not through a symlink either
+    match fs::symlink_metadata(&dir) {
+        Ok(meta) if meta.is_dir() => Ok(dir),
+        _ => Err(format!("Unknown session: {}", name)),
+    }
+}
+
+/// Moves sessions to the trash. One that can't be moved doesn't stop the rest.
+pub async fn move_to_trash(app: &AppHandle, ids: &[String]) -> Result<TrashReport, String> {
+    // Not while archiving or a file job may be using the session
+    let state = app.state::<AppState>();
+    let _job = state.transcription_lock.lock().await;
+    let in_use = state.sessions_in_use();
+
+    let recordings_dir = get_recordings_dir(app)?;
+    let trash_dir = get_trash_dir(app)?;
+
+    let mut report = TrashReport::default();
+    for id in ids {
+        match trash_one(&recordings_dir, &trash_dir, id, &in_use) {
+            Ok(trash_id) => report.trashed.push(trash_id),
+            Err(e) => {
+                println!("[Warn] {}", e);
+                report.failed.insert(id.clone(), e);
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn trash_one(
+    recordings_dir: &Path,
+    trash_dir: &Path,
+    id: &str,
+    in_use: &[PathBuf],
+) -> Result<String, String> {
+    let dir = folder_in(recordings_dir, id)?;
+    if in_use.contains(&dir) {
+        return Err("Can't delete a session that is being recorded or transcribed".into());
+    }
+
+    let now = Local::now();
+    let trash_id = format!("{}_{}", id, now.timestamp_millis());
+    let target = trash_dir.join(&trash_id);
+    fs::rename(&dir, &target).map_err(|e| format!("Failed to move {} to the trash: {}", id, e))?;
+
+    let marker = TrashMarker {
+        id: id.to_string(),
+        deleted_at: now.to_rfc3339(),
+    };
+    let written = serde_json::to_string_pretty(&marker)
+        .map_err(|e| e.to_string())
+        .and_then(|body| fs::write(target.join(MARKER_FILE), body).map_err(|e| e.to_string()));
+    // Still restorable: the id is also in the folder name
+    if let Err(e) = written {
+        println!("[Warn] Failed to write trash marker for {}: {}", id, e);
+    }
+
+    println!("[Rust] Moved session {} to the trash", id);
+    Ok(trash_id)
+}
+
+fn read_marker(dir: &Path, trash_id: &str) -> TrashMarker {
+    fs::read_to_string(dir.join(MARKER_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_else(|| TrashMarker {
+            id: trash_id
+                .rsplit_once('_')
+                .map_or(trash_id, |(id, _)| id)
+                .to_string(),
+            deleted_at: String::new(),
+        })
+}
+
+/// Trashed sessions, most recently deleted first.
+pub fn list(app: &AppHandle) -> Result<Vec<TrashItem>, String> {
+    let mut items = Vec::new();
+    for (trash_id, dir) in entries(app)? {
+        let marker = read_marker(&dir, &trash_id);
+        items.push(TrashItem {
+            id: marker.id,
+            title: load_meta(&dir).title,
+            text: crypto::read_to_string(&dir.join("transcript.txt")).unwrap_or_default(),
+            deleted_at: marker.deleted_at,
+            trash_id,
+        });
+    }
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(items)
+}
+
+fn entries(app: &AppHandle) -> Result<Vec<(String, PathBuf)>, String> {
+    let trash_dir = get_trash_dir(app)?;
+    Ok(fs::read_dir(&trash_dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter_map(|name| Some((name.clone(), folder_in(&trash_dir, &name).ok()?)))
+        .collect())
+}
+
+/// Moves a session back into the history. Returns its id, which only differs
+/// from the original if another session has taken it since.
+pub async fn restore(app: &AppHandle, trash_id: &str) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let _job = state.transcription_lock.lock().await;
+
+    let dir = folder_in(&get_trash_dir(app)?, trash_id)?;
+    let marker = read_marker(&dir, trash_id);
+    if !is_valid_session_id(&marker.id) {
+        return Err(format!("Invalid session id: {}", marker.id));
+    }
+
+    let recordings_dir = get_recordings_dir(app)?;
+    let id = next_free_session_id(&recordings_dir, &marker.id);
+    let _ = fs::remove_file(dir.join(MARKER_FILE));
+    fs::rename(&dir, recordings_dir.join(&id))
+        .map_err(|e| format!("Failed to restore {}: {}", marker.id, e))?;
+
+    println!("[Rust] Restored session {} from the trash", id);
+    Ok(id)
+}
+
+/// Deletes trashed sessions for good: the given ones, or all of them.
+/// Returns how many were removed.
+pub fn purge(app: &AppHandle, trash_ids: Option<&[String]>) -> Result<usize, String> {
+    let trash_dir = get_trash_dir(app)?;
+    let dirs = match trash_ids {
+        Some(ids) => ids
+            .iter()
+            .map(|id| folder_in(&trash_dir, id))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => entries(app)?.into_iter().map(|(_, dir)| dir).collect(),
+    };
+
+    for dir in &dirs {
+        fs::remove_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    if !dirs.is_empty() {
+        println!("[Rust] Purged {} sessions from the trash", dirs.len());
+    }
+    Ok(dirs.len())
+}
+
+/// Purges sessions deleted more than `days` ago; 0 keeps them.
+pub fn purge_expired(app: &AppHandle, days: u64) -> Result<usize, String> {
+    if days == 0 {
+        return Ok(0);
+    }
+    // A retention too long to represent never expires anything
+    let cutoff = match i64::try_from(days)
+        .ok()
+        .and_then(Duration::try_days)
+        .and_then(|d| Local::now().checked_sub_signed(d))
+    {
+        Some(cutoff) => cutoff,
+        None => return Ok(0),
+    };
+
+    let expired: Vec<String> = entries(app)?
+        .into_iter()
+        .filter(|(trash_id, dir)| {
+            // Without a readable marker, the folder's mtime is the best guess
+            let deleted_at = DateTime::parse_from_rfc3339(&read_marker(dir, trash_id).deleted_at)
+                .map(|t| t.with_timezone(&Local))
+                .ok()
+                .or_else(|| Some(fs::metadata(dir).ok()?.modified().ok()?.into()));
+            deleted_at.is_some_and(|t| t < cutoff)
+        })
+        .map(|(trash_id, _)| trash_id)
+        .collect();
+    purge(app, Some(&expired))
+}
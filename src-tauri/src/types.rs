@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tauri_plugin_shell::process::CommandChild;
 
@@ -9,6 +10,8 @@ pub struct RecordingSession {
     pub wav_path: PathBuf,
     pub transcript_path: PathBuf,
     pub child: CommandChild,
+    /// Per-session overrides (e.g. from the shortcut that started it).
+    pub options: DictationOptions,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -24,6 +27,17 @@ pub struct DownloadProgress {
     pub total_bytes: u64,
 }
 
+/// Payload of `transcription-progress`, emitted while whisper runs on a file.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TranscriptionProgress {
+    pub percent: u8,
+    /// Estimated seconds left, once enough work is done to extrapolate.
+    pub eta_secs: Option<u64>,
+    /// 1-based whisper run (chunk x pass) currently in progress.
+    pub chunk: usize,
+    pub chunks: usize,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AudioDevice {
     pub id: String,
@@ -35,4 +49,561 @@ pub struct HistoryItem {
     pub id: String,
     pub text: String,
     pub timestamp: String,
+    /// English translation, when the session was transcribed with `WhisperTask::Both`.
+    pub translation: Option<String>,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    pub favorite: bool,
+    /// `text` is a user correction; the whisper output is in the revisions.
+    pub edited: bool,
+}
+
+/// Narrows `get_history`; unset fields don't filter.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HistoryFilter {
+    /// Items must have all of these tags.
+    pub tags: Vec<String>,
+    pub pinned: Option<bool>,
+    pub favorite: Option<bool>,
+    /// Case-insensitive match against title and text.
+    pub query: Option<String>,
+    /// Session dates (`YYYY-MM-DD`), inclusive.
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl HistoryFilter {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+            && self.pinned.is_none()
+            && self.favorite.is_none()
+            && self.query.as_deref().is_none_or(|q| q.trim().is_empty())
+            && self.from.is_none()
+            && self.to.is_none()
+    }
+}
+
+/// A deleted session waiting in the trash.
+#[derive(Serialize, Clone)]
+pub struct TrashItem {
+    /// What `restore_history_item` and `purge_trash` take.
+    pub trash_id: String,
+    /// The session's id before it was deleted.
+    pub id: String,
+    pub title: Option<String>,
+    pub text: String,
+    pub deleted_at: String,
+}
+
+/// Result of moving sessions to the trash.
+#[derive(Serialize, Clone, Default)]
+pub struct TrashReport {
+    /// Trash ids of the sessions moved, in order.
+    pub trashed: Vec<String>,
+    /// Session id -> why it couldn't be moved.
+    pub failed: BTreeMap<String, String>,
+}
+
+/// Changes for `update_history_details`; unset fields are left as they are.
+/// An empty title clears it.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HistoryDetails {
+    pub title: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub pinned: Option<bool>,
+    pub favorite: Option<bool>,
+}
+
+/// Sessions `export_history` includes. Dates are `YYYY-MM-DD` and inclusive;
+/// non-empty `ids` selects exactly those sessions instead.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HistoryRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub ids: Vec<String>,
+}
+
+/// Result of `import_history`.
+#[derive(Serialize, Clone, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Sessions that were already in the history.
+    pub duplicates: usize,
+    /// Archive id -> id it was imported as, where the original was taken.
+    pub renamed: BTreeMap<String, String>,
+}
+
+/// What happens to a transcript once it is ready.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Copy to the clipboard and simulate Cmd+V.
+    Paste,
+    /// Copy to the clipboard only.
+    Copy,
+    /// Only keep it in history.
+    None,
+    /// Append to the dated Markdown note from `AppSettings::journal`.
+    Journal,
+}
+
+/// What whisper produces from the audio.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WhisperTask {
+    /// Text in the spoken language.
+    #[default]
+    Transcribe,
+    /// English translation (`--translate`).
+    Translate,
+    /// Both; the translation is delivered, the original kept alongside it.
+    Both,
+}
+
+impl WhisperTask {
+    /// One whisper run per entry, as its `--translate` flag. The original comes first.
+    pub fn passes(self) -> &'static [bool] {
+        match self {
+            WhisperTask::Transcribe => &[false],
+            WhisperTask::Translate => &[true],
+            WhisperTask::Both => &[false, true],
+        }
+    }
+}
+
+/// How a hook delivers a finished transcript.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HookKind {
+    /// Run `command` with the transcript on stdin and session details in `WHISPER_FLOW_*` env vars.
+    #[default]
+    Script,
+    /// POST the session as JSON to `url`.
+    Webhook,
+}
+
+/// Runs after every completed transcription (see hooks.rs).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TranscriptionHook {
+    pub name: String,
+    pub enabled: bool,
+    pub kind: HookKind,
+    pub command: String,
+    pub args: Vec<String>,
+    pub url: String,
+    /// Extra request headers, e.g. `Authorization`.
+    pub headers: BTreeMap<String, String>,
+    /// Limit for each attempt.
+    pub timeout_secs: u64,
+    /// Further attempts after a failure, with doubling delays.
+    pub retries: u32,
+}
+
+impl Default for TranscriptionHook {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            kind: HookKind::Script,
+            command: String::new(),
+            args: vec![],
+            url: String::new(),
+            headers: BTreeMap::new(),
+            timeout_secs: 10,
+            retries: 2,
+        }
+    }
+}
+
+/// Where and how `OutputMode::Journal` writes (placeholders are listed in journal.rs).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct JournalSettings {
+    /// Note to append to, e.g. `~/notes/{date}.md`.
+    pub path_pattern: String,
+    /// Written once, when the note is created. Empty for none.
+    pub frontmatter: String,
+    /// One entry; `{text}` is the transcript.
+    pub entry_template: String,
+    pub tags: Vec<String>,
+}
+
+impl Default for JournalSettings {
+    fn default() -> Self {
+        Self {
+            path_pattern: "~/Documents/Journal/{date}.md".into(),
+            frontmatter: "---\ndate: {date}\ntags: [{tags}]\n---\n".into(),
+            entry_template: "## {time}\n\n{text}\n".into(),
+            tags: vec!["journal".into()],
+        }
+    }
+}
+
+/// Retention rules for session audio, enforced by storage.rs. Text is always kept.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct StoragePolicy {
+    /// Remove `input.raw` once the 16 kHz WAV exists.
+    pub delete_raw_after_conversion: bool,
+    /// Remove audio of sessions recorded more than this many days ago. 0 keeps it forever.
+    pub audio_retention_days: u64,
+    /// Remove audio of the least recently used sessions while the recordings
+    /// folder is bigger than this. 0 means no cap.
+    pub max_total_mb: u64,
+    pub cleanup_interval_mins: u64,
+    /// Format audio is compressed to after transcription.
+    pub archive_format: AudioArchive,
+    /// Deleted sessions stay in the trash this many days. 0 keeps them until emptied.
+    pub trash_retention_days: u64,
+}
+
+impl Default for StoragePolicy {
+    fn default() -> Self {
+        Self {
+            delete_raw_after_conversion: false,
+            audio_retention_days: 0,
+            max_total_mb: 0,
+            cleanup_interval_mins: 60,
+            archive_format: AudioArchive::Wav,
+            trash_retention_days: 30,
+        }
+    }
+}
+
+/// How session audio is kept once transcribed.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioArchive {
+    /// Uncompressed 16 kHz WAV, ready for re-transcription.
+    #[default]
+    Wav,
+    /// Lossless, about half the size.
+    Flac,
+    /// Lossy, a few percent of the size; fine for speech.
+    Opus,
+}
+
+/// Result of `compress_recordings`.
+#[derive(Serialize, Clone, Default)]
+pub struct ArchiveReport {
+    pub sessions: usize,
+    pub saved_bytes: u64,
+    pub failed: usize,
+}
+
+/// Bytes used by the recordings folder, by category.
+#[derive(Serialize, Clone, Default)]
+pub struct StorageUsage {
+    pub sessions: usize,
+    /// `input.raw` captures.
+    pub raw_bytes: u64,
+    /// WAVs and other audio kept for re-transcription.
+    pub audio_bytes: u64,
+    /// Transcripts, segments and metadata.
+    pub text_bytes: u64,
+    pub other_bytes: u64,
+    /// Everything in the trash; counted in `total_bytes` too.
+    pub trash_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// What to do with a deep link that reads or writes a file.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FileAccessPolicy {
+    /// Show a confirmation dialog naming the file.
+    #[default]
+    Ask,
+    Allow,
+    Deny,
+}
+
+/// Optional overrides for a single dictation. Unset fields fall back to the
+/// options the UI last used.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct DictationOptions {
+    pub model_type: Option<String>,
+    pub language: Option<String>,
+    pub prompt: Option<String>,
+    pub task: Option<WhisperTask>,
+    pub output_mode: Option<OutputMode>,
+    pub post_processing: Option<PostProcessing>,
+}
+
+impl DictationOptions {
+    /// Layers `other` on top of `self`: every field set in `other` wins.
+    pub fn merged_with(self, other: &DictationOptions) -> DictationOptions {
+        DictationOptions {
+            model_type: other.model_type.clone().or(self.model_type),
+            language: other.language.clone().or(self.language),
+            prompt: other.prompt.clone().or(self.prompt),
+            task: other.task.or(self.task),
+            output_mode: other.output_mode.or(self.output_mode),
+            post_processing: other.post_processing.clone().or(self.post_processing),
+        }
+    }
+}
+
+/// Text clean-up applied to a dictation before it is saved and delivered.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct PostProcessing {
+    pub replacements: Vec<Replacement>,
+    /// Drop a single trailing `.` / `。` (handy for chat messages and shell commands).
+    pub remove_trailing_period: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct Replacement {
+    pub find: String,
+    pub replace: String,
+    pub ignore_case: bool,
+    /// Only match when not surrounded by letters or digits.
+    pub whole_word: bool,
+}
+
+/// A word or phrase the user keeps correcting in transcripts.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct TermCorrection {
+    /// As whisper wrote it.
+    pub find: String,
+    /// As the user corrected it.
+    pub replace: String,
+}
+
+/// Where an accepted correction goes.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectionTarget {
+    /// A whole-word replacement rule in a profile's post-processing.
+    Replacement,
+    /// `AppSettings::vocabulary`, so whisper gets the term right in the first place.
+    Vocabulary,
+}
+
+/// A correction made in several transcripts, proposed by `get_correction_suggestions`.
+#[derive(Serialize, Clone)]
+pub struct CorrectionSuggestion {
+    #[serde(flatten)]
+    pub correction: TermCorrection,
+    /// Sessions the correction was made in.
+    pub count: usize,
+    pub sessions: Vec<String>,
+    pub suggested_target: CorrectionTarget,
+}
+
+/// A named bundle of dictation options, selectable as the active profile.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DictationProfile {
+    pub name: String,
+    #[serde(flatten)]
+    pub options: DictationOptions,
+}
+
+/// Selects `profile` when dictating into a matching application.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AppProfileRule {
+    /// Case-insensitive substring of the app name, bundle id or window class.
+    pub app: String,
+    /// Optional case-insensitive substring of the focused window's title.
+    #[serde(default)]
+    pub title_contains: Option<String>,
+    pub profile: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    /// Each press starts or stops recording (handled by the frontend).
+    Toggle,
+    /// Hold to record, release to transcribe (handled entirely in Rust).
+    PushToTalk,
+    /// Discard the active recording, or abort the running transcription.
+    Cancel,
+    /// Deliver the most recent transcript again.
+    RepasteLast,
+    /// Transcribe the media file currently on the clipboard (e.g. copied in Finder).
+    TranscribeClipboardFile,
+    /// Make the binding's `profile_name` the active profile
+    /// (or cycle through the profiles when none is set).
+    SwitchProfile,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShortcutBinding {
+    /// Accelerator string, e.g. `Alt+Space`.
+    pub shortcut: String,
+    pub action: ShortcutAction,
+    /// Named profile to use for this binding.
+    #[serde(default)]
+    pub profile_name: Option<String>,
+    /// Inline overrides, applied on top of `profile_name`.
+    #[serde(default)]
+    pub profile: Option<DictationOptions>,
+}
+
+/// Registration result for one binding, so the UI can flag the broken ones.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ShortcutBindingStatus {
+    pub shortcut: String,
+    pub action: ShortcutAction,
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+/// Which audio channel to transcribe.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioChannel {
+    /// Downmix all channels.
+    #[default]
+    Mix,
+    Left,
+    Right,
+}
+
+/// Part of a media file to transcribe. Timestamps stay relative to the whole file.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MediaSlice {
+    pub start_secs: Option<f64>,
+    pub end_secs: Option<f64>,
+    /// Index among the file's audio streams (ffmpeg `-map 0:a:N`), e.g. 1 for an MKV's second track.
+    pub audio_stream: Option<usize>,
+    pub channel: AudioChannel,
+}
+
+impl MediaSlice {
+    /// Where the sliced audio starts in the original file.
+    pub fn offset_ms(&self) -> i64 {
+        (self.start_secs.unwrap_or(0.0) * 1000.0).round() as i64
+    }
+}
+
+/// Everything that controls how a media file is transcribed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTranscribeOptions {
+    pub model_type: String,
+    pub language: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub task: WhisperTask,
+    /// Return / store SRT instead of plain text.
+    #[serde(default)]
+    pub with_timestamps: bool,
+    /// Label speakers with tinydiarize (requires the `small.en-tdrz` model).
+    #[serde(default)]
+    pub diarize: bool,
+    /// Split SRT cues at word boundaries so none exceeds this many characters.
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+    #[serde(default)]
+    pub slice: MediaSlice,
+}
+
+/// Backend-owned preferences, persisted as `<app_data_dir>/settings.json`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Put the user's previous clipboard contents back after auto-paste.
+    pub restore_clipboard: bool,
+    /// How long to wait after the simulated paste before restoring.
+    pub clipboard_restore_delay_ms: u64,
+    pub shortcuts: Vec<ShortcutBinding>,
+    pub profiles: Vec<DictationProfile>,
+    /// Profile applied to every dictation, on top of the UI options.
+    pub active_profile: Option<String>,
+    /// Checked in order at stop time; the first match overrides the active profile.
+    pub app_profile_rules: Vec<AppProfileRule>,
+    /// Push-to-talk presses shorter than this are treated as accidental taps and discarded.
+    pub push_to_talk_min_hold_ms: u64,
+    // Last options used from the UI, so recordings driven from Rust
+    // (e.g. push-to-talk) behave the same as ones started from the frontend.
+    pub device_id: String,
+    pub model_type: String,
+    pub language: String,
+    pub prompt: String,
+    pub task: WhisperTask,
+    pub with_timestamps: bool,
+    /// Languages `auto` may resolve to (ISO codes, e.g. `en`, `de`). Empty allows any.
+    pub language_allow_list: Vec<String>,
+    /// How much audio the `auto` detection pass listens to.
+    pub language_detect_ms: u64,
+    /// Number of speaker labels diarized turns alternate between: 1 or 2
+    /// (see `transcript::MAX_DIARIZED_SPEAKERS`).
+    pub diarization_speakers: usize,
+    /// Refine word timestamps with whisper.cpp's DTW alignment (`-dtw`).
+    pub dtw_word_timestamps: bool,
+    /// Files longer than this are transcribed in chunks cut at silences. 0 disables chunking.
+    pub chunk_secs: u64,
+    /// Serve the local HTTP API (see api.rs).
+    pub api_enabled: bool,
+    /// `host:port` the API listens on. Keep it on loopback unless you mean to share it.
+    pub api_bind_address: String,
+    /// Bearer token required by the API; generated when the API is first enabled.
+    pub api_token: String,
+    /// Listen on the Unix control socket (see control.rs). Off by default.
+    pub control_socket_enabled: bool,
+    /// Confirmation for `whisper-flow://` links that touch files (see deeplink.rs).
+    pub deep_link_file_access: FileAccessPolicy,
+    /// Run in order after each transcription; results go to the session's hooks.jsonl.
+    pub hooks: Vec<TranscriptionHook>,
+    pub journal: JournalSettings,
+    pub storage: StoragePolicy,
+    /// Encrypt transcripts and audio at rest (see crypto.rs). Existing
+    /// sessions are converted by `migrate_encryption`.
+    pub encrypt_sessions: bool,
+    /// Names and jargon added to every whisper prompt so they're spelled right.
+    pub vocabulary: Vec<String>,
+    /// Correction suggestions the user turned down (see corrections.rs).
+    pub dismissed_corrections: Vec<TermCorrection>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            restore_clipboard: true,
+            clipboard_restore_delay_ms: 600,
+            shortcuts: vec![ShortcutBinding {
+                shortcut: "Alt+Space".into(),
+                action: ShortcutAction::Toggle,
+                profile_name: None,
+                profile: None,
+            }],
+            profiles: vec![],
+            active_profile: None,
+            app_profile_rules: vec![],
+            push_to_talk_min_hold_ms: 300,
+            device_id: "0".into(),
+            model_type: "large-v3-turbo".into(),
+            language: "auto".into(),
+            prompt: String::new(),
+            task: WhisperTask::Transcribe,
+            with_timestamps: false,
+            language_allow_list: vec![],
+            language_detect_ms: 10_000,
+            diarization_speakers: 2,
+            dtw_word_timestamps: false,
+            chunk_secs: 600,
+            api_enabled: false,
+            api_bind_address: "127.0.0.1:8178".into(),
+            api_token: String::new(),
+            control_socket_enabled: false,
+            deep_link_file_access: FileAccessPolicy::Ask,
+            hooks: vec![],
+            journal: JournalSettings::default(),
+            storage: StoragePolicy::default(),
+            encrypt_sessions: false,
+            vocabulary: vec![],
+            dismissed_corrections: vec![],
+        }
+    }
 }
@@ -1,8 +1,14 @@
 use crate::consts::MODELS;
-use chrono::Local;
+use crate::state::AppState;
+use crate::storage::{RAW_FILE, WAV_FILE};
+use chrono::{Local, NaiveDateTime};
+use std::ffi::OsStr;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::process::CommandEvent;
+use tauri_plugin_shell::ShellExt;
 
 pub fn ensure_dir(path: &Path) -> Result<(), String> {
     if !path.exists() {
@@ -20,15 +26,20 @@ pub fn get_recordings_dir(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(recordings_dir)
 }
 
+/// Deleted sessions, kept apart so history, storage cleanup and backups skip them.
+pub fn get_trash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let trash_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("trash");
+    ensure_dir(&trash_dir)?;
+    Ok(trash_dir)
+}
+
 pub fn get_model_info(app: &AppHandle, model_type: &str) -> Result<(PathBuf, String), String> {
     let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-
-    // 如果傳入的 model_type 不在 MODELS 裡面，預設使用 small
-    let target_model = if MODELS.iter().any(|(name, _)| *name == model_type) {
-        model_type
-    } else {
-        "large-v3-turbo"
-    };
+    let target_model = resolve_model(model_type);
 
     let filename = format!("ggml-{}.bin", target_model);
     let model_path = app_dir.join("models").join(&filename);
@@ -42,25 +53,109 @@ pub fn get_model_info(app: &AppHandle, model_type: &str) -> Result<(PathBuf, Str
     Ok((model_path, url))
 }
 
+/// The model `get_model_info` uses for `model_type`: itself if known, otherwise large-v3-turbo.
+pub fn resolve_model(model_type: &str) -> &'static str {
+    MODELS
+        .iter()
+        .map(|(name, _)| *name)
+        .find(|name| *name == model_type)
+        .unwrap_or("large-v3-turbo")
+}
+
+/// Session ids are folder names; anything else could escape the recordings dir.
+pub fn is_valid_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Folder of an existing session. Ids come from the frontend, links and
+/// sockets, so they are checked before touching the filesystem: `../..` or a
+/// symlinked folder could otherwise lead outside the recordings dir.
+pub fn existing_session_dir(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    if !is_valid_session_id(id) {
+        return Err(format!("Invalid session id: {}", id));
+    }
+    let session_dir = get_recordings_dir(app)?.join(id);
+    match std::fs::symlink_metadata(&session_dir) {
+        Ok(meta) if meta.is_dir() => Ok(session_dir),
+        _ => Err(format!("Unknown session: {}", id)),
+    }
+}
+
+/// Start time part of a session id.
+pub const SESSION_ID_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+/// Sessions that may start within the same second before giving up.
+const MAX_SESSIONS_PER_SECOND: u32 = 1000;
+
+/// Splits a session id (`YYYY-MM-DD_HH-MM-SS`, plus `-N` for the Nth session
+/// started in that second) into start time and counter. Older ids never
+/// have the suffix and count as 1. `None` for folders that aren't sessions.
+pub fn parse_session_id(id: &str) -> Option<(NaiveDateTime, u32)> {
+    let stamp = id.get(..19)?;
+    let started = NaiveDateTime::parse_from_str(stamp, SESSION_ID_FORMAT).ok()?;
+    let counter = match &id[19..] {
+        "" => 1,
+        rest => rest.strip_prefix('-')?.parse().ok()?,
+    };
+    Some((started, counter))
+}
+
 /// Creates a new per-recording session folder:
-/// <app_data_dir>/recordings/<YYYY-MM-DD_HH-MM-SS>/
-/// and returns paths for raw/wav/transcript files.
+/// <app_data_dir>/recordings/<YYYY-MM-DD_HH-MM-SS>[-N]/
+/// and returns paths for raw/wav/transcript files. The folder is always new:
+/// a session started in the same second gets the next free `-N`.
 pub fn new_session_paths(
     app: &AppHandle,
 ) -> Result<(String, PathBuf, PathBuf, PathBuf, PathBuf), String> {
     let recordings_dir = get_recordings_dir(app)?;
 
-    let session_id = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-    let session_dir = recordings_dir.join(&session_id);
-    ensure_dir(&session_dir)?;
+    let stamp = Local::now().format(SESSION_ID_FORMAT).to_string();
+    let (session_id, session_dir) = create_session_dir(&recordings_dir, &stamp)?;
 
-    let raw_path = session_dir.join("input.raw");
-    let wav_path = session_dir.join("input_16k.wav");
+    let raw_path = session_dir.join(RAW_FILE);
+    let wav_path = session_dir.join(WAV_FILE);
     let transcript_path = session_dir.join("transcript.txt");
 
     Ok((session_id, session_dir, raw_path, wav_path, transcript_path))
 }
 
+/// `id` if no session folder uses it, otherwise the next free same-second counter.
+pub fn next_free_session_id(recordings_dir: &Path, id: &str) -> String {
+    let (base, mut n) = match parse_session_id(id) {
+        Some((_, counter)) => (&id[..19], counter),
+        None => (id, 1),
+    };
+    loop {
+        let candidate = match n {
+            1 => base.to_string(),
+            n => format!("{}-{}", base, n),
+        };
+        if !recordings_dir.join(&candidate).exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+fn create_session_dir(recordings_dir: &Path, stamp: &str) -> Result<(String, PathBuf), String> {
+    for n in 1..=MAX_SESSIONS_PER_SECOND {
+        let id = match n {
+            1 => stamp.to_string(),
+            n => format!("{}-{}", stamp, n),
+        };
+        let dir = recordings_dir.join(&id);
+        // `create_dir` fails on an existing folder, so two concurrent callers never share one
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok((id, dir)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to create {}: {}", dir.display(), e)),
+        }
+    }
+    Err("Too many sessions started in the same second".into())
+}
+
 /// Returns true if a process with pid still exists.
 /// Uses: `kill -0 <pid>` (POSIX).
 pub fn process_is_alive(pid: u32) -> bool {
@@ -136,3 +231,172 @@ pub fn simulate_paste() {
         }
     }
 }
+
+pub struct SidecarOutput {
+    pub success: bool,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Runs a bundled sidecar (ffmpeg / whisper-cli) to completion.
+/// While it runs it is stored as the `processing_child`, so `abort_transcription` can kill it.
+pub async fn run_sidecar<I, S>(
+    app: &AppHandle,
+    name: &str,
+    args: I,
+) -> Result<SidecarOutput, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    run_sidecar_with(app, name, args, |_| {}).await
+}
+
+/// Same as `run_sidecar`, calling `on_stderr` with every stderr line (for progress parsing).
+pub async fn run_sidecar_with<I, S>(
+    app: &AppHandle,
+    name: &str,
+    args: I,
+    mut on_stderr: impl FnMut(&str),
+) -> Result<SidecarOutput, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let state = app.state::<AppState>();
+
+    let mut rx = {
+        let (rx, child) = app
+            .shell()
+            .sidecar(name)
+            .map_err(|e| e.to_string())?
+            .args(args)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+
+        let mut guard = state.processing_child.lock().unwrap();
+        *guard = Some(child);
+        rx
+    };
+
+    let mut output = SidecarOutput {
+        success: false,
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    };
+    while let Some(event) = rx.recv().await {
+        match event {
+            CommandEvent::Stdout(line) => output.stdout.extend(line),
+            CommandEvent::Stderr(line) => {
+                on_stderr(&String::from_utf8_lossy(&line));
+                output.stderr.extend(line);
+            }
+            CommandEvent::Terminated(payload) => {
+                output.success = payload.code == Some(0);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    // Clear child
+    {
+        let mut guard = state.processing_child.lock().unwrap();
+        *guard = None;
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wf-utils-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parses_ids_with_and_without_suffix() {
+        let (started, counter) = parse_session_id("2024-05-01_09-30-15").unwrap();
+        assert_eq!(started.to_string(), "2024-05-01 09:30:15");
+        assert_eq!(counter, 1);
+
+        let (started, counter) = parse_session_id("2024-05-01_09-30-15-12").unwrap();
+        assert_eq!(started.to_string(), "2024-05-01 09:30:15");
+        assert_eq!(counter, 12);
+    }
+
+    #[test]
+    fn rejects_folders_that_are_not_sessions() {
+        assert!(parse_session_id("").is_none());
+        assert!(parse_session_id("trash").is_none());
+        assert!(parse_session_id("2024-05-01_09-30-15_2").is_none());
+        assert!(parse_session_id("2024-05-01_09-30-15-").is_none());
+        assert!(parse_session_id("2024-13-01_09-30-15").is_none());
+    }
+
+    #[test]
+    fn validates_session_ids() {
+        assert!(is_valid_session_id("2024-05-01_09-30-15"));
+        assert!(is_valid_session_id("2024-05-01_09-30-15-3"));
+        assert!(!is_valid_session_id(""));
+        assert!(!is_valid_session_id(".."));
+        assert!(!is_valid_session_id("../2024-05-01_09-30-15"));
+        assert!(!is_valid_session_id("2024-05-01/09-30-15"));
+        assert!(!is_valid_session_id("2024-05-01\\09-30-15"));
+    }
+
+    #[test]
+    fn same_second_sessions_get_a_counter() {
+        let dir = scratch_dir("create");
+        let stamp = "2024-05-01_09-30-15";
+
+        let (first, first_dir) = create_session_dir(&dir, stamp).unwrap();
+        let (second, _) = create_session_dir(&dir, stamp).unwrap();
+        let (third, _) = create_session_dir(&dir, stamp).unwrap();
+        assert_eq!(first, stamp);
+        assert_eq!(second, "2024-05-01_09-30-15-2");
+        assert_eq!(third, "2024-05-01_09-30-15-3");
+        assert!(first_dir.is_dir());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn gives_up_after_max_sessions_per_second() {
+        let dir = scratch_dir("overflow");
+        let stamp = "2024-05-01_09-30-15";
+
+        for _ in 0..MAX_SESSIONS_PER_SECOND {
+            create_session_dir(&dir, stamp).unwrap();
+        }
+        assert!(create_session_dir(&dir, stamp).is_err());
+        assert!(dir
+            .join(format!("{}-{}", stamp, MAX_SESSIONS_PER_SECOND))
+            .is_dir());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn next_free_id_skips_taken_counters() {
+        let dir = scratch_dir("next-free");
+        let id = "2024-05-01_09-30-15";
+        assert_eq!(next_free_session_id(&dir, id), id);
+
+        std::fs::create_dir(dir.join(id)).unwrap();
+        std::fs::create_dir(dir.join(format!("{}-2", id))).unwrap();
+        assert_eq!(next_free_session_id(&dir, id), "2024-05-01_09-30-15-3");
+        // A suffixed id continues from its own counter
+        assert_eq!(
+            next_free_session_id(&dir, "2024-05-01_09-30-15-2"),
+            "2024-05-01_09-30-15-3"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,294 @@
+//! whisper-cli helpers shared by dictation and file transcription.
+
+use crate::session::SessionMeta;
+use crate::state::AppState;
+use crate::transcript::{parse_whisper_json, Segment};
+use crate::utils::{run_sidecar, run_sidecar_with};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+pub struct LanguageDetection {
+    pub language: String,
+    pub probability: f32,
+}
+
+/// Result of resolving the requested language before transcription.
+pub struct ResolvedLanguage {
+    /// Value to pass to `-l`.
+    pub language: String,
+    /// Set when `auto` triggered a detection pass.
+    pub detection: Option<LanguageDetection>,
+    /// The detected language wasn't allowed and `language` is the best-scoring
+    /// allow-listed one instead.
+    pub fallback: bool,
+}
+
+impl ResolvedLanguage {
+    pub fn record_in(&self, meta: &mut SessionMeta) {
+        meta.language = Some(self.language.clone());
+        meta.detected_language = self.detection.as_ref().map(|d| d.language.clone());
+        meta.language_probability = self.detection.as_ref().map(|d| d.probability);
+        meta.language_fallback = self.fallback;
+    }
+}
+
+/// Runs whisper's language identification on the first `window_ms` of audio
+/// (`-dl` exits right after detection, so this is cheap).
+///
+/// `Ok(None)` means whisper ran but reported no language; `Err` means the run
+/// itself failed or was aborted.
+pub async fn detect_language(
+    app: &AppHandle,
+    model_path: &Path,
+    wav_path: &Path,
+    window_ms: u64,
+) -> Result<Option<LanguageDetection>, String> {
+    let output = run_sidecar(
+        app,
+        "whisper-cli",
+        [
+            "-m",
+            model_path
+                .to_str()
+                .ok_or_else(|| "Invalid model path".to_string())?,
+            "-f",
+            wav_path
+                .to_str()
+                .ok_or_else(|| "Invalid wav path".to_string())?,
+            "-t",
+            "8",
+            "-l",
+            "auto",
+            "-dl",
+            "-d",
+            &window_ms.to_string(),
+        ],
+    )
+    .await?;
+
+    if !output.success {
+        return Err("Language detection failed or was aborted".into());
+    }
+
+    // "whisper_full_with_state: auto-detected language: en (p = 0.973258)"
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let line = match stderr
+        .lines()
+        .find(|l| l.contains("auto-detected language:"))
+    {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+
+    let rest = line.split("auto-detected language:").nth(1).unwrap_or("");
+    let language = rest.split_whitespace().next().unwrap_or("").to_string();
+    let probability = rest
+        .split("p = ")
+        .nth(1)
+        .and_then(|p| {
+            p.trim_end_matches(|c: char| c == ')' || c.is_whitespace())
+                .parse()
+                .ok()
+        })
+        .unwrap_or(0.0);
+
+    if language.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(LanguageDetection {
+        language,
+        probability,
+    }))
+}
+
+/// Mean word probability when the first `window_ms` of audio is decoded as
+/// `language`. whisper-cli only reports the top detected language, so this is
+/// how allow-listed candidates are ranked against each other.
+async fn score_language(
+    app: &AppHandle,
+    model_path: &Path,
+    wav_path: &Path,
+    language: &str,
+    window_ms: u64,
+) -> Result<f32, String> {
+    let args = [
+        "-m".to_string(),
+        model_path.to_string_lossy().to_string(),
+        "-t".to_string(),
+        "8".to_string(),
+        "-l".to_string(),
+        language.to_string(),
+        "-d".to_string(),
+        window_ms.to_string(),
+    ];
+    let json_base = wav_path.with_file_name(format!("language-score-{}", language));
+    let run = run_whisper(app, &args, wav_path, &json_base, None, |_| {}).await?;
+
+    let probabilities: Vec<f32> = run
+        .segments
+        .iter()
+        .flat_map(|s| &s.words)
+        .map(|w| w.probability)
+        .collect();
+    if probabilities.is_empty() {
+        return Ok(0.0);
+    }
+    Ok(probabilities.iter().sum::<f32>() / probabilities.len() as f32)
+}
+
+/// Turns `auto` into a concrete language, using the allow-list and detection
+/// window from the settings.
+///
+/// With a non-empty allow-list, a detected language outside the list falls
+/// back to the allowed language whisper decodes with the highest confidence.
+/// When no language is reported, whisper auto-detects during transcription as
+/// before.
+pub async fn resolve_language(
+    app: &AppHandle,
+    model_path: &Path,
+    wav_path: &Path,
+    requested: &str,
+) -> Result<ResolvedLanguage, String> {
+    if requested != "auto" {
+        return Ok(ResolvedLanguage {
+            language: requested.to_string(),
+            detection: None,
+            fallback: false,
+        });
+    }
+
+    // English-only models (`ggml-small.en-tdrz.bin`) can't identify languages
+    let english_only = model_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.contains(".en"));
+    if english_only {
+        return Ok(ResolvedLanguage {
+            language: "en".into(),
+            detection: None,
+            fallback: false,
+        });
+    }
+
+    let (allow_list, window_ms) = {
+        let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+        (settings.language_allow_list, settings.language_detect_ms)
+    };
+
+    let detection = match detect_language(app, model_path, wav_path, window_ms).await? {
+        Some(d) => d,
+        None => {
+            println!("[Warn] Language detection reported no language, leaving it to whisper");
+            return Ok(ResolvedLanguage {
+                language: "auto".into(),
+                detection: None,
+                fallback: false,
+            });
+        }
+    };
+
+    println!(
+        "[Rust] Detected language: {} (p = {:.3})",
+        detection.language, detection.probability
+    );
+
+    if allow_list.is_empty() || allow_list.contains(&detection.language) {
+        return Ok(ResolvedLanguage {
+            language: detection.language.clone(),
+            detection: Some(detection),
+            fallback: false,
+        });
+    }
+
+    let mut best = (allow_list[0].clone(), f32::MIN);
+    if allow_list.len() > 1 {
+        for candidate in &allow_list {
+            let score = score_language(app, model_path, wav_path, candidate, window_ms).await?;
+            println!("[Rust] Allow-listed {} scores {:.3}", candidate, score);
+            if score > best.1 {
+                best = (candidate.clone(), score);
+            }
+        }
+    }
+
+    println!(
+        "[Rust] {} is not in the allow-list, using {}",
+        detection.language, best.0
+    );
+
+    Ok(ResolvedLanguage {
+        language: best.0,
+        detection: Some(detection),
+        fallback: true,
+    })
+}
+
+pub struct WhisperRun {
+    pub stdout: String,
+    pub stderr: String,
+    /// Timed segments from the JSON output; empty when whisper wrote none.
+    pub segments: Vec<Segment>,
+}
+
+/// Runs whisper-cli on `wav_path` with `args`, writing JSON to `<json_base>.json`
+/// (parsed and removed afterwards) and forwarding `-pp` progress to `on_progress`.
+pub async fn run_whisper(
+    app: &AppHandle,
+    args: &[String],
+    wav_path: &Path,
+    json_base: &Path,
+    speaker_count: Option<usize>,
+    mut on_progress: impl FnMut(u32),
+) -> Result<WhisperRun, String> {
+    let mut args = args.to_vec();
+    args.extend([
+        "-f".to_string(),
+        wav_path.to_string_lossy().to_string(),
+        // -ojf writes timed segments as JSON to <-of>.json
+        "-ojf".to_string(),
+        "-of".to_string(),
+        json_base.to_string_lossy().to_string(),
+        "-pp".to_string(),
+    ]);
+
+    let output = run_sidecar_with(app, "whisper-cli", args, |line| {
+        if let Some(percent) = parse_progress(line) {
+            on_progress(percent);
+        }
+    })
+    .await?;
+
+    if !output.success {
+        return Err("Whisper transcription failed or was aborted".into());
+    }
+
+    // Keep the timed segments for exports; stdout remains the fallback
+    let json_path = json_base.with_extension("json");
+    let segments = match tokio::fs::read(&json_path).await {
+        Ok(raw) => {
+            let _ = tokio::fs::remove_file(&json_path).await;
+            parse_whisper_json(&String::from_utf8_lossy(&raw), speaker_count)?
+        }
+        Err(_) => {
+            println!("Warning: JSON output not found, falling back to stdout");
+            vec![]
+        }
+    };
+
+    Ok(WhisperRun {
+        stdout: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        segments,
+    })
+}
+
+/// "whisper_print_progress_callback: progress =  45%" -> 45
+fn parse_progress(line: &str) -> Option<u32> {
+    line.split("progress =")
+        .nth(1)?
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .ok()
+}
@@ -6,6 +6,7 @@ import { OnboardingScreen } from "./components/Onboarding/OnboardingScreen";
 import { SettingsCard } from "./components/SettingsCard";
 import { HistorySection } from "./components/HistorySection";
 import { DragOverlay } from "./components/DragOverlay";
+import { FileProgress } from "./components/FileProgress";
 import { ShortcutOverlay } from "./components/ShortcutOverlay";
 import { ModelDownloadScreen } from "./components/ModelDownloadScreen";
 import { getCurrentWindow } from "@tauri-apps/api/window";
@@ -36,6 +37,7 @@ function App() {
     isLoading,
     downloading,
     downloadProgress,
+    transcriptionProgress,
     error,
     recordingsDir,
     history,
@@ -153,6 +155,10 @@ function App() {
 
       {error && <div className="error-toast">{error}</div>}
 
+      {isLoading && transcriptionProgress && (
+        <FileProgress progress={transcriptionProgress} t={t} />
+      )}
+
       {/* Overlays */}
       {isRecordingShortcut && (
         <ShortcutOverlay onClose={() => setIsRecordingShortcut(false)} t={t} />
@@ -0,0 +1,31 @@
+import { TranscriptionProgress } from "../constants";
+
+interface FileProgressProps {
+  progress: TranscriptionProgress;
+  t: any;
+}
+
+function formatEta(secs: number) {
+  const m = Math.floor(secs / 60);
+  const s = secs % 60;
+  return m > 0 ? `${m}:${String(s).padStart(2, "0")}` : `${s}s`;
+}
+
+export function FileProgress({ progress, t }: FileProgressProps) {
+  return (
+    <div className="file-progress">
+      <div className="file-progress-header">
+        <span>{t.fileProgressTitle}</span>
+        <span>{progress.percent}%</span>
+      </div>
+      <div className="file-progress-bar">
+        <div className="fill" style={{ width: `${progress.percent}%` }} />
+      </div>
+      <div className="progress-text">
+        {progress.chunks > 1 && `${t.fileProgressChunk} ${progress.chunk}/${progress.chunks}`}
+        {progress.chunks > 1 && progress.eta_secs !== null && " · "}
+        {progress.eta_secs !== null && t.fileProgressEta.replace("{eta}", formatEta(progress.eta_secs))}
+      </div>
+    </div>
+  );
+}
@@ -18,4 +18,12 @@ export interface HistoryItem {
   id: string;
   text: string;
   timestamp: string;
+  translation?: string | null;
+}
+
+export interface TranscriptionProgress {
+  percent: number;
+  eta_secs: number | null;
+  chunk: number;
+  chunks: number;
 }
@@ -2,7 +2,7 @@ import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
-import { ModelStatus, AudioDevice, HistoryItem } from "../constants";
+import { ModelStatus, AudioDevice, HistoryItem, TranscriptionProgress } from "../constants";
 import { UILanguage } from "../i18n";
 
 export function useAppLogic() {
@@ -43,6 +43,7 @@ export function useAppLogic() {
   const [isLoading, setIsLoading] = useState(false); // 轉錄中 (Whisper)
   const [downloading, setDownloading] = useState(false);
   const [downloadProgress, setDownloadProgress] = useState(0);
+  const [transcriptionProgress, setTranscriptionProgress] = useState<TranscriptionProgress | null>(null);
 
   // 結果與錯誤
   const [transcription, setTranscription] = useState("");
@@ -170,6 +171,10 @@ export function useAppLogic() {
     let unlistenShortcut: (() => void) | undefined;
     let unlistenDownload: (() => void) | undefined;
     let unlistenReady: (() => void) | undefined;
+    let unlistenRecordingState: (() => void) | undefined;
+    let unlistenResult: (() => void) | undefined;
+    let unlistenTranscriptionError: (() => void) | undefined;
+    let unlistenTranscriptionProgress: (() => void) | undefined;
 
     // Drag events
     let unlistenDragEnter: (() => void) | undefined;
@@ -215,6 +220,41 @@ export function useAppLogic() {
         recordStartTime.current = Date.now();
       });
 
+      // 由 Rust 驅動的流程 (按住說話、快捷鍵轉錄剪貼簿檔案等)，這裡只同步 UI 狀態
+      unlistenRecordingState = await listen<string>("recording-state", (event) => {
+        switch (event.payload) {
+          case "transcribing":
+            setIsRecording(false);
+            setIsLoading(true);
+            break;
+          case "cancelled":
+            setIsStarting(false);
+            setIsRecording(false);
+            break;
+          case "done":
+            setIsLoading(false);
+            fetchHistory();
+            break;
+        }
+      });
+
+      unlistenResult = await listen<string>("transcription-result", (event) => {
+        setTranscription(event.payload);
+      });
+
+      unlistenTranscriptionError = await listen<string>("transcription-error", (event) => {
+        setIsStarting(false);
+        setIsRecording(false);
+        setIsLoading(false);
+        setError(`轉錄錯誤: ${event.payload}`);
+      });
+
+      // 長檔案轉錄進度 (百分比、預估剩餘秒數、分段)
+      unlistenTranscriptionProgress = await listen<TranscriptionProgress>(
+        "transcription-progress",
+        (event) => setTranscriptionProgress(event.payload)
+      );
+
       // --- Drag & Drop Listeners ---
       unlistenDragEnter = await listen("tauri://drag-enter", () => {
         setIsDragging(true);
@@ -239,6 +279,10 @@ export function useAppLogic() {
       if (unlistenShortcut) unlistenShortcut();
       if (unlistenDownload) unlistenDownload();
       if (unlistenReady) unlistenReady();
+      if (unlistenRecordingState) unlistenRecordingState();
+      if (unlistenResult) unlistenResult();
+      if (unlistenTranscriptionError) unlistenTranscriptionError();
+      if (unlistenTranscriptionProgress) unlistenTranscriptionProgress();
       if (unlistenDragEnter) unlistenDragEnter();
       if (unlistenDragLeave) unlistenDragLeave();
       if (unlistenDragDrop) unlistenDragDrop();
@@ -252,6 +296,11 @@ export function useAppLogic() {
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, [selectedModel]);
 
+  // 轉錄結束後清除進度，避免下次顯示舊的百分比
+  useEffect(() => {
+    if (!isLoading) setTranscriptionProgress(null);
+  }, [isLoading]);
+
   // 快捷鍵錄製邏輯
   useEffect(() => {
     if (!isRecordingShortcut) return;
@@ -386,6 +435,7 @@ export function useAppLogic() {
     setIsLoading(true);
     setError(null);
     setTranscription("");
+    setTranscriptionProgress(null);
     console.log("Processing file:", filePath);
 
     try {
@@ -473,6 +523,7 @@ export function useAppLogic() {
     isLoading,
     downloading,
     downloadProgress,
+    transcriptionProgress,
     transcription, setTranscription,
     error, setError,
     recordingsDir,
@@ -39,6 +39,9 @@ export const translations = {
         dragText: "釋放以匯入檔案",
         dragSubtextSRT: "將生成 SRT 字幕",
         dragSubtextText: "純文字模式",
+        fileProgressTitle: "轉錄中",
+        fileProgressChunk: "片段",
+        fileProgressEta: "剩餘約 {eta}",
         shortcutOverlayMsg: "請按下新的組合鍵...",
         langAuto: "自動辨識",
         langZh: "中文",
@@ -82,6 +85,9 @@ export const translations = {
         dragText: "释放以导入文件",
         dragSubtextSRT: "将生成 SRT 字幕",
         dragSubtextText: "纯文本模式",
+        fileProgressTitle: "转录中",
+        fileProgressChunk: "片段",
+        fileProgressEta: "剩余约 {eta}",
         shortcutOverlayMsg: "请按下新的组合键...",
         langAuto: "自动识别",
         langZh: "中文",
@@ -125,6 +131,9 @@ export const translations = {
         dragText: "Drop to import file",
         dragSubtextSRT: "Generating SRT subtitles",
         dragSubtextText: "Plain text mode",
+        fileProgressTitle: "Transcribing",
+        fileProgressChunk: "Part",
+        fileProgressEta: "About {eta} left",
         shortcutOverlayMsg: "Press your new shortcut...",
         langAuto: "Auto",
         langZh: "Chinese",